@@ -0,0 +1,384 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tokio::task;
+
+use crate::{batch, filter, health, preferences::PROJECT_DIRS};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawCurveData {
+    pub id: usize,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: [f32; 3],
+    /// Filtered `(temp, seconds since charge)` samples.
+    pub points: Vec<(f32, f32)>,
+    /// The readings before filtering, the points in older files.
+    #[serde(default)]
+    pub raw: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub filter: filter::Settings,
+    #[serde(default)]
+    pub health: health::Report,
+}
+
+impl RawCurveData {
+    /// Temperature at `time` seconds, linearly interpolated between samples.
+    pub fn temp_at(&self, time: f32) -> Option<f32> {
+        let after = self.points.iter().position(|(_, t)| *t >= time)?;
+        let (temp, t) = self.points[after];
+        match after.checked_sub(1).map(|before| self.points[before]) {
+            Some((prev_temp, prev_t)) if t > prev_t => {
+                Some(prev_temp + (temp - prev_temp) * (time - prev_t) / (t - prev_t))
+            }
+            _ => Some(temp),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventKind {
+    DryEnd,
+    FirstCrack,
+    SecondCrack,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [
+        EventKind::DryEnd,
+        EventKind::FirstCrack,
+        EventKind::SecondCrack,
+    ];
+
+    /// Identifier used in exported files and commands.
+    pub fn key(self) -> &'static str {
+        match self {
+            EventKind::DryEnd => "dry_end",
+            EventKind::FirstCrack => "first_crack",
+            EventKind::SecondCrack => "second_crack",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<EventKind> {
+        EventKind::ALL.into_iter().find(|kind| kind.key() == key)
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EventKind::DryEnd => "Dry end",
+            EventKind::FirstCrack => "First crack",
+            EventKind::SecondCrack => "Second crack",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub kind: EventKind,
+    /// Seconds since charge.
+    pub time: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawRoastData {
+    /// Empty in files saved before batch metadata was recorded.
+    #[serde(default)]
+    pub start: batch::Start,
+    pub end: Option<batch::End>,
+    pub weight_loss: Option<f32>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    pub data: Vec<RawCurveData>,
+    /// The curves recorded before charge, at negative times.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preheat: Vec<RawCurveData>,
+}
+
+/// Key figures of a roast, taken from the bean probe.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// Lowest bean temperature between charge and first crack,
+    /// `(seconds, temp)`.
+    pub turning_point: Option<(f32, f32)>,
+    pub first_crack: Option<(f32, f32)>,
+    pub drop: Option<(f32, f32)>,
+    /// Development time ratio, in percent of the total time.
+    pub development: Option<f32>,
+    pub total_time: f32,
+}
+
+impl RawRoastData {
+    /// The bean probe is the first configured sensor.
+    pub fn bean(&self) -> Option<&RawCurveData> {
+        self.data.first()
+    }
+
+    pub fn event(&self, kind: EventKind) -> Option<f32> {
+        self.events
+            .iter()
+            .find(|event| event.kind == kind)
+            .map(|event| event.time)
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let Some(bean) = self.bean() else {
+            return Metrics::default();
+        };
+        let temp_at = |time: f32| {
+            bean.points
+                .iter()
+                .find(|(_, t)| *t >= time)
+                .or(bean.points.last())
+                .map(|(temp, _)| *temp)
+        };
+
+        let first_crack_time = self.event(EventKind::FirstCrack);
+        let turning_point = bean
+            .points
+            .iter()
+            .filter(|(_, time)| {
+                *time >= 0.0 && first_crack_time.is_none_or(|first_crack| *time < first_crack)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(temp, time)| (*time, *temp));
+        let first_crack = first_crack_time.and_then(|time| Some((time, temp_at(time)?)));
+        let drop = bean.points.last().map(|(temp, time)| (*time, *temp));
+        let total_time = drop.map(|(time, _)| time).unwrap_or_default();
+
+        Metrics {
+            turning_point,
+            first_crack,
+            drop,
+            development: first_crack
+                .filter(|_| total_time > 0.0)
+                .map(|(time, _)| (total_time - time) / total_time * 100.0),
+            total_time,
+        }
+    }
+}
+
+/// A roast saved in the data directory.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub roast: RawRoastData,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} – {}",
+            self.roast.start.started_at.format("%d-%m-%Y %H:%M"),
+            self.roast.start.recipe.as_deref().unwrap_or("No recipe")
+        )
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+pub fn dir() -> PathBuf {
+    PROJECT_DIRS.data_dir().to_path_buf()
+}
+
+/// Saves a roast under its start time and machine, never over another
+/// roast.
+pub fn save(roast: &RawRoastData) -> Result<PathBuf, Box<dyn Error>> {
+    let machine: String = roast
+        .start
+        .machine
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let mut path = dir().join("_");
+    let file_name = format!(
+        "roast_{}_{}.json",
+        roast.start.started_at.format("%d-%m-%Y_%Hh%Mm%S"),
+        machine
+    );
+    path.set_file_name(file_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = serde_json::to_string(roast)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    if let Err(error) = file.write_all(contents.as_bytes()) {
+        // A partial file would keep the roast from being saved again.
+        drop(file);
+        let _ = fs::remove_file(&path);
+        return Err(error.into());
+    }
+    Ok(path)
+}
+
+/// Reads a roast file, filling in what files of earlier versions lack.
+pub fn read(path: &Path) -> Result<RawRoastData, Box<dyn Error>> {
+    let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let has_batch = value.get("start").is_some();
+    let mut roast: RawRoastData = serde_json::from_value(value)?;
+
+    if !has_batch {
+        roast.start.started_at = saved_at(path);
+    }
+    for curve in &mut roast.data {
+        if curve.raw.is_empty() {
+            curve.raw = curve.points.clone();
+        }
+    }
+
+    Ok(roast)
+}
+
+/// When a roast without batch metadata was saved: from its file name,
+/// `roast_%d-%m-%Y_%Hh%M`, or else the last time the file was modified.
+fn saved_at(path: &Path) -> DateTime<Local> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str()?.strip_prefix("roast_"))
+        .and_then(|date| NaiveDateTime::parse_from_str(date, "%d-%m-%Y_%Hh%M").ok())
+        .and_then(|date| Local.from_local_datetime(&date).earliest())
+        .or_else(|| Some(fs::metadata(path).ok()?.modified().ok()?.into()))
+        .unwrap_or_default()
+}
+
+pub fn load(path: PathBuf) -> Result<Entry, Box<dyn Error>> {
+    let roast = read(&path)?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(Entry { name, path, roast })
+}
+
+/// Loads every saved roast, oldest first.
+pub fn list() -> Vec<Entry> {
+    let Ok(entries) = fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+
+    let mut roasts: Vec<Entry> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("roast_"))
+        })
+        .filter_map(|path| load(path).ok())
+        .collect();
+    roasts.sort_by_key(|entry| entry.roast.start.started_at);
+
+    roasts
+}
+
+/// Loads every saved roast outside of the async runtime, see `list`.
+pub async fn load_all() -> Vec<Entry> {
+    task::spawn_blocking(list).await.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roast(bean: Vec<(f32, f32)>, events: Vec<Event>) -> RawRoastData {
+        RawRoastData {
+            start: batch::Start {
+                lot: None,
+                recipe: None,
+                green_weight: 1.0,
+                ambient_temp: None,
+                humidity: None,
+                operator: String::new(),
+                machine: String::new(),
+                order: None,
+                planned: None,
+                started_at: Local::now(),
+            },
+            end: None,
+            weight_loss: None,
+            events,
+            data: vec![RawCurveData {
+                id: 0,
+                name: "Bean".to_string(),
+                color: [0.0, 0.5, 1.0],
+                raw: bean.clone(),
+                points: bean,
+                filter: filter::Settings::default(),
+                health: health::Health::default().report(),
+            }],
+            preheat: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn turning_point_before_first_crack() {
+        // The probe dips at charge, then falls again after first crack.
+        let bean = vec![
+            (180.0, 0.0),
+            (95.0, 60.0),
+            (120.0, 120.0),
+            (200.0, 480.0),
+            (190.0, 500.0),
+            (90.0, 560.0),
+        ];
+        let metrics = roast(
+            bean,
+            vec![Event {
+                kind: EventKind::FirstCrack,
+                time: 480.0,
+            }],
+        )
+        .metrics();
+
+        assert_eq!(metrics.turning_point, Some((60.0, 95.0)));
+        assert_eq!(metrics.first_crack, Some((480.0, 200.0)));
+        assert_eq!(metrics.drop, Some((560.0, 90.0)));
+        assert_eq!(metrics.development, Some(80.0 / 560.0 * 100.0));
+    }
+
+    #[test]
+    fn roasts_saved_before_batch_metadata() {
+        let dir = std::env::temp_dir().join(format!("archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("roast_05-03-2024_09h30.json");
+        fs::write(
+            &path,
+            r#"{"data":[{"id":0,"points":[[200.0,0.0],[150.0,30.0]]}]}"#,
+        )
+        .unwrap();
+
+        let entry = load(path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entry.name, "roast_05-03-2024_09h30");
+        assert_eq!(
+            entry.roast.start.started_at,
+            Local.with_ymd_and_hms(2024, 3, 5, 9, 30, 0).unwrap()
+        );
+        assert_eq!(entry.roast.start.recipe, None);
+        let bean = entry.roast.bean().unwrap();
+        assert_eq!(bean.raw, bean.points);
+        assert_eq!(entry.roast.metrics().total_time, 30.0);
+    }
+}
@@ -0,0 +1,282 @@
+use chrono::{DateTime, Local};
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    widget::{column, pick_list, row, text, text_input},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{inventory::Lot, production::PlannedBatch};
+
+/// Context of a batch captured when the roast starts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Start {
+    pub lot: Option<u64>,
+    pub recipe: Option<String>,
+    /// Green weight in kg.
+    pub green_weight: f32,
+    pub ambient_temp: Option<f32>,
+    pub humidity: Option<f32>,
+    pub operator: String,
+    pub machine: String,
+    #[serde(default)]
+    pub order: Option<String>,
+    /// The batch of the production plan it was loaded from.
+    #[serde(default)]
+    pub planned: Option<u64>,
+    pub started_at: DateTime<Local>,
+}
+
+/// Results of a batch captured once the beans are dropped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct End {
+    /// Roasted weight in kg.
+    pub roasted_weight: f32,
+    pub color: Option<f32>,
+    pub notes: String,
+}
+
+/// Weight loss in percent of the green weight.
+pub fn weight_loss(start: &Start, end: &End) -> f32 {
+    (start.green_weight - end.roasted_weight) / start.green_weight * 100.0
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    GreenWeight,
+    AmbientTemp,
+    Humidity,
+    Operator,
+    Machine,
+    RoastedWeight,
+    Color,
+    Notes,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LotSelected(Lot),
+    RecipeSelected(String),
+    FieldChanged(Field, String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Form {
+    lot: Option<u64>,
+    recipe: Option<String>,
+    green_weight: String,
+    ambient_temp: String,
+    humidity: String,
+    operator: String,
+    machine: String,
+    order: Option<String>,
+    planned: Option<u64>,
+    roasted_weight: String,
+    color: String,
+    notes: String,
+    error: Option<String>,
+}
+
+fn required(value: &str, name: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(number) if number > 0.0 => Ok(number),
+        _ => Err(format!("{} must be a positive number", name)),
+    }
+}
+
+fn optional(value: &str, name: &str) -> Result<Option<f32>, String> {
+    if value.trim().is_empty() {
+        Ok(None)
+    } else {
+        value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} must be a number", name))
+    }
+}
+
+impl Form {
+    pub fn for_machine(machine: &str) -> Self {
+        Form {
+            machine: machine.to_string(),
+            ..Form::default()
+        }
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::LotSelected(lot) => self.lot = Some(lot.id),
+            Message::RecipeSelected(recipe) => self.recipe = Some(recipe),
+            Message::FieldChanged(field, value) => {
+                *match field {
+                    Field::GreenWeight => &mut self.green_weight,
+                    Field::AmbientTemp => &mut self.ambient_temp,
+                    Field::Humidity => &mut self.humidity,
+                    Field::Operator => &mut self.operator,
+                    Field::Machine => &mut self.machine,
+                    Field::RoastedWeight => &mut self.roasted_weight,
+                    Field::Color => &mut self.color,
+                    Field::Notes => &mut self.notes,
+                } = value;
+            }
+        }
+    }
+
+    /// Fills the start fields from a planned batch of the production queue.
+    pub fn preload(&mut self, batch: &PlannedBatch) {
+        self.lot = batch.lot;
+        self.recipe = batch.recipe.clone();
+        self.green_weight = batch.target_weight.to_string();
+        self.order = Some(batch.order.clone()).filter(|order| !order.is_empty());
+        self.planned = Some(batch.id);
+    }
+
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    /// Validates the start fields, keeping the operator and machine for the
+    /// next batch.
+    pub fn start(&mut self) -> Option<Start> {
+        let start = (|| -> Result<Start, String> {
+            Ok(Start {
+                lot: self.lot,
+                recipe: self.recipe.clone(),
+                green_weight: required(&self.green_weight, "Green weight")?,
+                ambient_temp: optional(&self.ambient_temp, "Ambient temperature")?,
+                humidity: optional(&self.humidity, "Humidity")?,
+                operator: self.operator.trim().to_string(),
+                machine: self.machine.trim().to_string(),
+                order: self.order.take(),
+                planned: self.planned.take(),
+                started_at: Local::now(),
+            })
+        })();
+
+        self.take(start)
+    }
+
+    /// Validates the end fields, they are kept until `clear_end` in case
+    /// the roast has to be saved again.
+    pub fn end(&mut self) -> Option<End> {
+        let end = (|| -> Result<End, String> {
+            Ok(End {
+                roasted_weight: required(&self.roasted_weight, "Roasted weight")?,
+                color: optional(&self.color, "Color")?,
+                notes: self.notes.trim().to_string(),
+            })
+        })();
+
+        self.take(end)
+    }
+
+    /// Empties the end fields once the roast is saved.
+    pub fn clear_end(&mut self) {
+        self.roasted_weight.clear();
+        self.color.clear();
+        self.notes.clear();
+    }
+
+    fn take<T>(&mut self, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.error = None;
+                Some(value)
+            }
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    fn input(
+        &self,
+        label: &'static str,
+        placeholder: &'static str,
+        field: Field,
+    ) -> Element<Message> {
+        let value = match field {
+            Field::GreenWeight => &self.green_weight,
+            Field::AmbientTemp => &self.ambient_temp,
+            Field::Humidity => &self.humidity,
+            Field::Operator => &self.operator,
+            Field::Machine => &self.machine,
+            Field::RoastedWeight => &self.roasted_weight,
+            Field::Color => &self.color,
+            Field::Notes => &self.notes,
+        };
+
+        row![
+            text(label).width(180),
+            text_input(placeholder, value)
+                .on_input(move |value| Message::FieldChanged(field, value))
+        ]
+        .align_y(Alignment::Center)
+        .into()
+    }
+
+    pub fn view_start<'a>(
+        &'a self,
+        lots: &'a [Lot],
+        recipes: &'a [String],
+    ) -> Element<'a, Message> {
+        let lot = self.lot.and_then(|id| lots.iter().find(|lot| lot.id == id));
+
+        column![
+            row![
+                text("Green lot").width(180),
+                pick_list(lots, lot, Message::LotSelected)
+                    .placeholder("Select a lot...")
+                    .width(Fill),
+            ]
+            .align_y(Alignment::Center),
+            row![
+                text("Recipe").width(180),
+                pick_list(recipes, self.recipe.as_ref(), Message::RecipeSelected)
+                    .placeholder("Select a recipe...")
+                    .width(Fill),
+            ]
+            .align_y(Alignment::Center),
+            self.input("Green weight (kg)", "12.0", Field::GreenWeight),
+            self.input("Ambient temp (°C)", "21", Field::AmbientTemp),
+            self.input("Humidity (%)", "45", Field::Humidity),
+            self.input("Operator", "Name", Field::Operator),
+            self.input("Machine", "Roaster", Field::Machine),
+        ]
+        .push_maybe(self.error().map(|error| text(error).style(text::danger)))
+        .spacing(10)
+        .max_width(600)
+        .into()
+    }
+
+    pub fn view_end(&self, start: &Start) -> Element<Message> {
+        let loss = required(&self.roasted_weight, "")
+            .ok()
+            .map(|roasted_weight| {
+                weight_loss(
+                    start,
+                    &End {
+                        roasted_weight,
+                        color: None,
+                        notes: String::new(),
+                    },
+                )
+            });
+
+        column![
+            self.input("Roasted weight (kg)", "10.2", Field::RoastedWeight),
+            text(match loss {
+                Some(loss) => format!("Weight loss: {:.1} %", loss),
+                None => format!("Green weight: {:.2} kg", start.green_weight),
+            }),
+            self.input("Color reading", "Agtron", Field::Color),
+            self.input("Notes", "Notes", Field::Notes),
+        ]
+        .push_maybe(self.error().map(|error| text(error).style(text::danger)))
+        .spacing(10)
+        .max_width(600)
+        .into()
+    }
+}
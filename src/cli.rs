@@ -0,0 +1,214 @@
+use iced::{Size, Theme};
+use std::{error::Error, fs, path::Path};
+
+use crate::{
+    archive::{self, Entry},
+    convert,
+    data::Recipe,
+    format::{duration, point},
+    logger,
+    report::{self, Format, Resolution},
+};
+
+const USAGE: &str = "\
+Usage: cambio-torrefaction [COMMAND]
+
+Without a command the application window is opened.
+
+Commands:
+  list                              List the saved roasts
+  summary <roast>                   Print the key metrics of a roast
+  convert <input> <output>          Convert between .json, .csv and .alog (Artisan)
+  validate <recipe>...              Check recipe files (.json or .toml)
+  render <roast> <output> [options] Render the chart to .png or .svg
+  report <roast> <output>           Write the roast report to .svg or .pdf
+  log [options]                     Record roasts from the configured sensors
+
+Log options:
+  --port <port>        Also accept commands on this local TCP port
+  --recipe <name>      Recipe of the recorded roasts
+  --weight <kg>        Default green weight
+  --operator <name>    Operator of the recorded roasts
+  --machine <name>     Roaster of the recorded roasts, and of the sensors read
+
+Render options:
+  --width <pixels>     Image width (default 1920)
+  --height <pixels>    Image height (default 1080)
+  --theme <name>       Theme name, e.g. Light or Dark (default Light)
+
+A <roast> is either the path of a roast file or the name of a saved roast.";
+
+/// Finds a roast by path, or by name among the saved roasts.
+fn roast(argument: &str) -> Result<Entry, Box<dyn Error>> {
+    let path = Path::new(argument);
+    if path.is_file() {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return Ok(Entry {
+            name,
+            path: path.to_path_buf(),
+            roast: convert::read(path)?,
+        });
+    }
+
+    archive::list()
+        .into_iter()
+        .find(|entry| entry.name == argument)
+        .ok_or_else(|| format!("no roast named {}", argument).into())
+}
+
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn list() {
+    for entry in archive::list() {
+        let metrics = entry.roast.metrics();
+        println!(
+            "{}\t{}\t{}\t{}",
+            entry.name,
+            entry,
+            duration(metrics.total_time),
+            entry
+                .roast
+                .weight_loss
+                .map_or("–".to_string(), |loss| format!("{:.1} %", loss))
+        );
+    }
+}
+
+fn summary(entry: &Entry) {
+    let metrics = entry.roast.metrics();
+
+    println!("{}", entry);
+    println!("Turning point: {}", point(metrics.turning_point));
+    println!("First crack:   {}", point(metrics.first_crack));
+    println!("Drop:          {}", point(metrics.drop));
+    println!(
+        "DTR:           {}",
+        metrics
+            .development
+            .map_or("–".to_string(), |dtr| format!("{:.1} %", dtr))
+    );
+    println!("Total time:    {}", duration(metrics.total_time));
+    if let Some(loss) = entry.roast.weight_loss {
+        println!("Weight loss:   {:.1} %", loss);
+    }
+}
+
+/// Returns whether every recipe is valid.
+fn validate(paths: &[String]) -> bool {
+    let mut valid = true;
+    for path in paths {
+        match Recipe::load(Path::new(path)) {
+            Ok(recipe) => {
+                let problems = recipe.validate();
+                if problems.is_empty() {
+                    println!("{}: ok", path);
+                } else {
+                    valid = false;
+                    for problem in problems {
+                        println!("{}: {}", path, problem);
+                    }
+                }
+            }
+            Err(error) => {
+                valid = false;
+                println!("{}: {}", path, error);
+            }
+        }
+    }
+    valid
+}
+
+fn render(entry: &Entry, output: &Path, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let default = Resolution::default();
+    let resolution = Resolution::new(
+        option(args, "--width").map_or(Ok(default.width), str::parse)?,
+        option(args, "--height").map_or(Ok(default.height), str::parse)?,
+    );
+    let theme = match option(args, "--theme") {
+        Some(name) => Theme::ALL
+            .iter()
+            .find(|theme| {
+                theme
+                    .to_string()
+                    .replace(' ', "")
+                    .eq_ignore_ascii_case(name)
+            })
+            .cloned()
+            .ok_or_else(|| format!("unknown theme {}", name))?,
+        None => Theme::Light,
+    };
+
+    let image = match output.extension().and_then(|extension| extension.to_str()) {
+        Some("png") => report::png(&entry.roast, resolution, &theme)?,
+        Some("svg") => report::chart(
+            &entry.roast,
+            Size::new(resolution.width as f32, resolution.height as f32),
+            &theme,
+        )
+        .into_bytes(),
+        _ => return Err("charts can be rendered to .png or .svg".into()),
+    };
+    fs::write(output, image)?;
+    Ok(())
+}
+
+fn write_report(entry: &Entry, output: &Path) -> Result<(), Box<dyn Error>> {
+    let format = match output.extension().and_then(|extension| extension.to_str()) {
+        Some("svg") => Format::Svg,
+        Some("pdf") => Format::Pdf,
+        _ => return Err("reports can be written to .svg or .pdf".into()),
+    };
+    fs::write(output, report::render(&entry.roast, format)?)?;
+    Ok(())
+}
+
+/// Runs a command given on the command line and returns the exit code.
+pub fn run(args: &[String]) -> Result<i32, Box<dyn Error>> {
+    let argument = |i: usize| -> Result<&str, Box<dyn Error>> {
+        args.get(i)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing argument\n\n{}", USAGE).into())
+    };
+
+    match args.first().map(String::as_str) {
+        Some("list") => list(),
+        Some("summary") => summary(&roast(argument(1)?)?),
+        Some("convert") => {
+            let input = convert::read(Path::new(argument(1)?))?;
+            convert::write(&input, Path::new(argument(2)?))?;
+        }
+        Some("validate") => {
+            argument(1)?;
+            if !validate(&args[1..]) {
+                return Ok(1);
+            }
+        }
+        Some("render") => render(&roast(argument(1)?)?, Path::new(argument(2)?), args)?,
+        Some("report") => write_report(&roast(argument(1)?)?, Path::new(argument(2)?))?,
+        Some("log") => logger::run(logger::Options {
+            port: option(args, "--port").map(str::parse).transpose()?,
+            recipe: option(args, "--recipe").map(str::to_string),
+            green_weight: option(args, "--weight")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            operator: option(args, "--operator").unwrap_or_default().to_string(),
+            machine: option(args, "--machine").unwrap_or_default().to_string(),
+        })?,
+        Some("help" | "--help" | "-h") => println!("{}", USAGE),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Ok(2);
+        }
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,325 @@
+use iced::{
+    Alignment, Color, Element,
+    Length::Fill,
+    Task, Theme,
+    widget::{
+        button, canvas, checkbox, column, container, horizontal_space, pick_list, row, scrollable,
+        text,
+    },
+};
+use std::fmt;
+use tokio::task;
+
+use crate::{
+    archive::{self, Entry, EventKind, Metrics},
+    format::{duration, point},
+    report::{self, Format, Resolution},
+    roast::{CurveFit, CurveSettings, Overlay, Roast},
+};
+
+const COLORS: [Color; 6] = [
+    Color::from_rgb(0.0, 0.5, 1.0),
+    Color::from_rgb(1.0, 0.3, 0.0),
+    Color::from_rgb(0.2, 0.7, 0.2),
+    Color::from_rgb(0.7, 0.3, 0.9),
+    Color::from_rgb(0.9, 0.7, 0.0),
+    Color::from_rgb(0.0, 0.7, 0.7),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Charge,
+    FirstCrack,
+}
+
+impl Align {
+    const ALL: [Align; 2] = [Align::Charge, Align::FirstCrack];
+}
+
+impl fmt::Display for Align {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Align::Charge => write!(f, "Align on charge"),
+            Align::FirstCrack => write!(f, "Align on first crack"),
+        }
+    }
+}
+
+/// Renders an export outside of the async runtime, the PDF and PNG
+/// conversions take long enough to freeze the window.
+async fn render(
+    export: impl FnOnce() -> Result<String, String> + Send + 'static,
+) -> Result<String, String> {
+    task::spawn_blocking(export)
+        .await
+        .unwrap_or_else(|error| Err(error.to_string()))
+}
+
+#[derive(Debug)]
+pub struct Comparison {
+    roasts: Vec<Entry>,
+    selected: Vec<String>,
+    align: Align,
+    overlay: Overlay,
+    resolution: Resolution,
+    theme: Theme,
+    status: Option<Result<String, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Toggled(String, bool),
+    AlignSelected(Align),
+    RorToggled(bool),
+    Export(String, Format),
+    ExportImage(String),
+    Exported(Result<String, String>),
+    ResolutionSelected(Resolution),
+    ThemeSelected(Theme),
+    Refresh,
+    Loaded(Vec<Entry>),
+}
+
+impl Comparison {
+    pub fn new() -> Self {
+        Comparison {
+            roasts: Vec::new(),
+            selected: Vec::new(),
+            align: Align::default(),
+            overlay: Overlay::default(),
+            resolution: Resolution::default(),
+            theme: Theme::Light,
+            status: None,
+        }
+    }
+
+    /// Reads the saved roasts again, off the UI thread.
+    pub fn refresh(&self) -> Task<Message> {
+        Task::perform(archive::load_all(), Message::Loaded)
+    }
+
+    fn loaded(&mut self, roasts: Vec<Entry>) {
+        self.roasts = roasts;
+        self.selected
+            .retain(|name| self.roasts.iter().any(|entry| entry.name == *name));
+        self.rebuild();
+    }
+
+    fn roast(&self, name: &str) -> Option<Entry> {
+        self.roasts.iter().find(|entry| entry.name == name).cloned()
+    }
+
+    fn selected(&self) -> impl Iterator<Item = &Entry> {
+        self.selected
+            .iter()
+            .filter_map(|name| self.roasts.iter().find(|entry| entry.name == *name))
+    }
+
+    /// Where a roast is aligned, none if it lacks the alignment point.
+    fn anchor(&self, entry: &Entry) -> Option<f32> {
+        match self.align {
+            Align::Charge => Some(0.0),
+            Align::FirstCrack => entry.roast.event(EventKind::FirstCrack),
+        }
+    }
+
+    /// Rebuilds the overlaid curves, shifting each roast so the alignment
+    /// point lands at the same place on a shared time axis. Roasts without
+    /// that point are left out.
+    fn rebuild(&mut self) {
+        let aligned: Vec<(&Entry, &Color, f32)> = self
+            .selected()
+            .zip(COLORS.iter().cycle())
+            .filter_map(|(entry, color)| Some((entry, color, self.anchor(entry)?)))
+            .collect();
+        let latest = aligned
+            .iter()
+            .map(|(_, _, anchor)| *anchor)
+            .fold(0.0, f32::max);
+        let end = aligned
+            .iter()
+            .map(|(entry, _, anchor)| latest - anchor + entry.roast.metrics().total_time)
+            .fold(17.0 * 60.0, f32::max);
+
+        let roasts = aligned
+            .into_iter()
+            .map(|(entry, color, anchor)| {
+                let mut roast = Roast::from_saved(&entry.roast, latest - anchor);
+                roast.settings = CurveSettings {
+                    min: 0.0,
+                    max: end + 10.0,
+                    fit: CurveFit::Normal,
+                };
+                (roast, *color)
+            })
+            .collect();
+
+        self.overlay.roasts = roasts;
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Toggled(name, true) => {
+                if !self.selected.contains(&name) {
+                    self.selected.push(name);
+                }
+                self.rebuild();
+            }
+            Message::Toggled(name, false) => {
+                self.selected.retain(|selected| *selected != name);
+                self.rebuild();
+            }
+            Message::AlignSelected(align) => {
+                self.align = align;
+                self.rebuild();
+            }
+            Message::RorToggled(ror) => self.overlay.ror = ror,
+            Message::Export(name, format) => {
+                if let Some(entry) = self.roast(&name) {
+                    self.status = None;
+                    return Task::perform(
+                        render(move || {
+                            report::export(&entry, format)
+                                .map(|path| format!("Report saved to {}", path.display()))
+                                .map_err(|error| error.to_string())
+                        }),
+                        Message::Exported,
+                    );
+                }
+            }
+            Message::ExportImage(name) => {
+                if let Some(entry) = self.roast(&name) {
+                    let (resolution, theme) = (self.resolution, self.theme.clone());
+                    self.status = None;
+                    return Task::perform(
+                        render(move || {
+                            report::export_png(&entry, resolution, &theme)
+                                .map(|path| format!("Chart saved to {}", path.display()))
+                                .map_err(|error| error.to_string())
+                        }),
+                        Message::Exported,
+                    );
+                }
+            }
+            Message::Exported(status) => self.status = Some(status),
+            Message::ResolutionSelected(resolution) => self.resolution = resolution,
+            Message::ThemeSelected(theme) => self.theme = theme,
+            Message::Refresh => return self.refresh(),
+            Message::Loaded(roasts) => self.loaded(roasts),
+        }
+
+        Task::none()
+    }
+
+    fn metrics_row<'a>(name: String, color: Color, metrics: Metrics) -> Element<'a, Message> {
+        let cell = |value: Option<String>| {
+            text(value.unwrap_or_else(|| "–".to_string()))
+                .width(Fill)
+                .into()
+        };
+
+        row([
+            text(name).color(color).width(Fill).into(),
+            cell(Some(point(metrics.turning_point))),
+            cell(Some(point(metrics.first_crack))),
+            cell(Some(point(metrics.drop))),
+            cell(metrics.development.map(|dtr| format!("{:.1} %", dtr))),
+            cell(Some(duration(metrics.total_time))),
+        ])
+        .spacing(10)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = text("Compare roasts").size(30);
+
+        let header = row![
+            pick_list(Align::ALL, Some(self.align), Message::AlignSelected),
+            checkbox("Rate of rise", self.overlay.ror).on_toggle(Message::RorToggled),
+            horizontal_space(),
+            text("Image"),
+            pick_list(
+                Resolution::ALL,
+                Some(self.resolution),
+                Message::ResolutionSelected
+            ),
+            pick_list(Theme::ALL, Some(&self.theme), Message::ThemeSelected),
+            button("Refresh")
+                .on_press(Message::Refresh)
+                .style(button::secondary),
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center);
+
+        let roasts = scrollable(
+            column(self.roasts.iter().rev().map(|entry| {
+                let name = entry.name.clone();
+                row![
+                    checkbox(entry.to_string(), self.selected.contains(&entry.name))
+                        .on_toggle(move |checked| Message::Toggled(name.clone(), checked))
+                        .width(Fill)
+                ]
+                .extend(Format::ALL.iter().map(|format| {
+                    button(text(format.to_string()).size(12))
+                        .on_press(Message::Export(entry.name.clone(), *format))
+                        .style(button::secondary)
+                        .into()
+                }))
+                .push(
+                    button(text("PNG").size(12))
+                        .on_press(Message::ExportImage(entry.name.clone()))
+                        .style(button::secondary),
+                )
+                .spacing(5)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(5),
+        )
+        .width(450)
+        .height(Fill);
+
+        let metrics = column![
+            row([
+                "Roast",
+                "Turning point",
+                "First crack",
+                "Drop",
+                "DTR",
+                "Total"
+            ]
+            .into_iter()
+            .map(|header| text(header).style(text::secondary).width(Fill).into()))
+            .spacing(10)
+        ]
+        .extend(
+            self.selected()
+                .zip(COLORS.iter().cycle())
+                .map(|(entry, color)| {
+                    let name = match self.anchor(entry) {
+                        Some(_) => entry.to_string(),
+                        None => format!("{} (no first crack, not shown)", entry),
+                    };
+                    Self::metrics_row(name, *color, entry.roast.metrics())
+                }),
+        )
+        .spacing(5);
+
+        container(
+            column![
+                title,
+                header,
+                row![roasts, canvas(&self.overlay).width(Fill).height(Fill)].spacing(20),
+                metrics,
+            ]
+            .push_maybe(self.status.as_ref().map(|status| match status {
+                Ok(message) => text(message).style(text::success),
+                Err(error) => text(error).style(text::danger),
+            }))
+            .spacing(20),
+        )
+        .padding(20)
+        .into()
+    }
+}
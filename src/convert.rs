@@ -0,0 +1,540 @@
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde_json::{Value, json};
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+use crate::{
+    archive::{self, Event, EventKind, RawCurveData, RawRoastData},
+    batch,
+    discovery::COLORS,
+    filter, health,
+};
+
+/// Formats a roast can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Artisan,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, Box<dyn Error>> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(Format::Json),
+            Some("csv") => Ok(Format::Csv),
+            Some("alog") => Ok(Format::Artisan),
+            _ => Err(format!("unknown roast format for {}", path.display()).into()),
+        }
+    }
+}
+
+pub fn read(path: &Path) -> Result<RawRoastData, Box<dyn Error>> {
+    match Format::from_path(path)? {
+        Format::Json => archive::read(path),
+        Format::Csv => from_csv(&fs::read_to_string(path)?),
+        Format::Artisan => from_artisan(&fs::read_to_string(path)?),
+    }
+}
+
+pub fn write(roast: &RawRoastData, path: &Path) -> Result<(), Box<dyn Error>> {
+    let string = match Format::from_path(path)? {
+        Format::Json => serde_json::to_string(roast)?,
+        Format::Csv => to_csv(roast),
+        Format::Artisan => to_artisan(roast),
+    };
+    fs::write(path, string)?;
+    Ok(())
+}
+
+/// Batch metadata for files that do not carry any.
+fn unknown_batch() -> batch::Start {
+    batch::Start {
+        lot: None,
+        recipe: None,
+        green_weight: 0.0,
+        ambient_temp: None,
+        humidity: None,
+        operator: String::new(),
+        machine: String::new(),
+        order: None,
+        planned: None,
+        started_at: Local::now(),
+    }
+}
+
+fn curve(id: usize, name: &str, color: [f32; 3], points: Vec<(f32, f32)>) -> RawCurveData {
+    RawCurveData {
+        id,
+        name: name.to_string(),
+        color,
+        raw: points.clone(),
+        points,
+        filter: filter::Settings::default(),
+        health: health::Health::default().report(),
+    }
+}
+
+/// One row per bean sample, the other curves interpolated at its time.
+pub fn to_csv(roast: &RawRoastData) -> String {
+    let mut csv = String::from("time,event");
+    for curve in &roast.data {
+        let _ = write!(csv, ",{}", curve.name.replace(',', " "));
+    }
+    csv.push('\n');
+
+    let Some(bean) = roast.bean() else {
+        return csv;
+    };
+    let mut events: Vec<&Event> = roast.events.iter().collect();
+    for (_, time) in &bean.points {
+        let _ = write!(csv, "{:.2},", time);
+        if let Some(position) = events.iter().position(|event| event.time <= *time) {
+            csv.push_str(events.remove(position).kind.key());
+        }
+        for curve in &roast.data {
+            match curve.temp_at(*time) {
+                Some(temp) => {
+                    let _ = write!(csv, ",{:.2}", temp);
+                }
+                None => csv.push(','),
+            }
+        }
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn from_csv(string: &str) -> Result<RawRoastData, Box<dyn Error>> {
+    let mut lines = string.lines();
+    let header: Vec<&str> = lines.next().ok_or("empty CSV file")?.split(',').collect();
+    if header.len() < 3 || header[0] != "time" || header[1] != "event" {
+        return Err("expected a `time,event,<curves...>` header".into());
+    }
+
+    let mut data: Vec<RawCurveData> = header[2..]
+        .iter()
+        .enumerate()
+        .map(|(id, name)| curve(id, name, COLORS[id % COLORS.len()], Vec::new()))
+        .collect();
+    let mut events = Vec::new();
+
+    for (number, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        let time: f32 = fields[0]
+            .trim()
+            .parse()
+            .map_err(|_| format!("line {}: invalid time", number + 2))?;
+
+        if let Some(kind) = fields
+            .get(1)
+            .and_then(|key| EventKind::from_key(key.trim()))
+        {
+            events.push(Event { kind, time });
+        }
+
+        for (curve, field) in data.iter_mut().zip(fields.iter().skip(2)) {
+            if let Ok(temp) = field.trim().parse() {
+                curve.points.push((temp, time));
+                curve.raw.push((temp, time));
+            }
+        }
+    }
+
+    Ok(RawRoastData {
+        start: unknown_batch(),
+        end: None,
+        weight_loss: None,
+        events,
+        data,
+        preheat: Vec::new(),
+    })
+}
+
+/// Artisan stores the index of charge, dry end, first crack start/end,
+/// second crack start/end, drop and cool end in `timeindex`.
+const ARTISAN_EVENTS: [(usize, EventKind); 3] = [
+    (1, EventKind::DryEnd),
+    (2, EventKind::FirstCrack),
+    (4, EventKind::SecondCrack),
+];
+
+/// Writes an Artisan `.alog` profile. Artisan reads it as a Python literal,
+/// which is JSON without `true`, `false` and `null`.
+pub fn to_artisan(roast: &RawRoastData) -> String {
+    let bean = roast
+        .bean()
+        .map(|bean| bean.points.as_slice())
+        .unwrap_or_default();
+    let timex: Vec<f32> = bean.iter().map(|(_, time)| *time).collect();
+    let bt: Vec<f32> = bean.iter().map(|(temp, _)| *temp).collect();
+    let et: Vec<f32> = match roast.data.get(1) {
+        Some(exhaust) => timex
+            .iter()
+            .map(|time| exhaust.temp_at(*time).unwrap_or(-1.0))
+            .collect(),
+        None => vec![-1.0; timex.len()],
+    };
+
+    let mut timeindex = [0, 0, 0, 0, 0, 0, timex.len().saturating_sub(1), 0];
+    for (index, kind) in ARTISAN_EVENTS {
+        if let Some(time) = roast.event(kind) {
+            timeindex[index] = timex.iter().position(|t| *t >= time).unwrap_or_default();
+        }
+    }
+
+    let start = &roast.start;
+    json!({
+        "version": "2.0",
+        "mode": "C",
+        "title": start.recipe.clone().unwrap_or_default(),
+        "operator": start.operator,
+        "roastertype": start.machine,
+        "roastisodate": start.started_at.format("%Y-%m-%d").to_string(),
+        "roasttime": start.started_at.format("%H:%M:%S").to_string(),
+        "weight": [
+            start.green_weight,
+            roast.end.as_ref().map_or(0.0, |end| end.roasted_weight),
+            "Kg"
+        ],
+        "roastingnotes": roast.end.as_ref().map(|end| end.notes.clone()).unwrap_or_default(),
+        "timex": timex,
+        "temp1": et,
+        "temp2": bt,
+        "timeindex": timeindex,
+    })
+    .to_string()
+}
+
+pub fn from_artisan(string: &str) -> Result<RawRoastData, Box<dyn Error>> {
+    let profile: Value = serde_json::from_str(&python_to_json(string))?;
+    let numbers = |key: &str| -> Vec<f32> {
+        profile[key]
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_f64())
+                    .map(|value| value as f32)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let timex = numbers("timex");
+    let samples = |temps: Vec<f32>| -> Vec<(f32, f32)> {
+        temps
+            .into_iter()
+            .zip(timex.iter().copied())
+            .filter(|(temp, _)| *temp >= 0.0)
+            .collect()
+    };
+    let data = vec![
+        curve(0, "Bean", [0.0, 0.5, 1.0], samples(numbers("temp2"))),
+        curve(1, "Exhaust", [1.0, 0.0, 0.0], samples(numbers("temp1"))),
+    ];
+
+    let timeindex = numbers("timeindex");
+    let charge = timeindex
+        .first()
+        .and_then(|index| timex.get(*index as usize))
+        .copied();
+    let events = ARTISAN_EVENTS
+        .into_iter()
+        .filter_map(|(index, kind)| {
+            let index = *timeindex.get(index).filter(|index| **index > 0.0)? as usize;
+            Some(Event {
+                kind,
+                time: timex.get(index)? - charge.unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    let started_at =
+        NaiveDate::parse_from_str(profile["roastisodate"].as_str().unwrap_or(""), "%Y-%m-%d")
+            .ok()
+            .map(|date| {
+                date.and_time(
+                    NaiveTime::parse_from_str(
+                        profile["roasttime"].as_str().unwrap_or(""),
+                        "%H:%M:%S",
+                    )
+                    .unwrap_or_default(),
+                )
+            })
+            .and_then(|datetime| Local.from_local_datetime(&datetime).single())
+            .unwrap_or_else(Local::now);
+    let weight = numbers("weight");
+    let unit = profile["weight"][2].as_str().unwrap_or("g");
+    let kg = |weight: f32| {
+        if unit.eq_ignore_ascii_case("kg") {
+            weight
+        } else {
+            weight / 1000.0
+        }
+    };
+
+    let start = batch::Start {
+        recipe: profile["title"]
+            .as_str()
+            .filter(|title| !title.is_empty())
+            .map(str::to_string),
+        green_weight: weight.first().copied().map(kg).unwrap_or_default(),
+        operator: profile["operator"].as_str().unwrap_or_default().to_string(),
+        machine: profile["roastertype"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        started_at,
+        ..unknown_batch()
+    };
+    let end = weight
+        .get(1)
+        .copied()
+        .filter(|roasted| *roasted > 0.0)
+        .map(|roasted| batch::End {
+            roasted_weight: kg(roasted),
+            color: None,
+            notes: profile["roastingnotes"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        });
+
+    Ok(RawRoastData {
+        weight_loss: end.as_ref().map(|end| batch::weight_loss(&start, end)),
+        start,
+        end,
+        events,
+        data: shift(data, charge.unwrap_or_default()),
+        preheat: Vec::new(),
+    })
+}
+
+/// Moves the curves so the time axis starts at charge.
+fn shift(mut data: Vec<RawCurveData>, charge: f32) -> Vec<RawCurveData> {
+    for curve in &mut data {
+        for (_, time) in curve.points.iter_mut().chain(curve.raw.iter_mut()) {
+            *time -= charge;
+        }
+        curve.points.retain(|(_, time)| *time >= 0.0);
+        curve.raw.retain(|(_, time)| *time >= 0.0);
+    }
+    data
+}
+
+/// Rewrites a Python literal (single quoted strings, tuples, `True`,
+/// `False`, `None`) as JSON.
+fn python_to_json(python: &str) -> String {
+    let mut json = String::with_capacity(python.len());
+    let mut chars = python.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            quote @ ('\'' | '"') => {
+                json.push('"');
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some('\'') => json.push('\''),
+                            Some(escaped) => {
+                                json.push('\\');
+                                json.push(escaped);
+                            }
+                            None => {}
+                        },
+                        c if c == quote => break,
+                        '"' => json.push_str("\\\""),
+                        c => json.push(c),
+                    }
+                }
+                json.push('"');
+            }
+            '(' => json.push('['),
+            ')' => json.push(']'),
+            c if c.is_ascii_alphabetic() => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    word.push(c);
+                }
+                json.push_str(match word.as_str() {
+                    // Unicode string prefix of Python 2 profiles.
+                    "u" if chars.peek().is_some_and(|c| *c == '\'' || *c == '"') => "",
+                    "True" => "true",
+                    "False" => "false",
+                    "None" => "null",
+                    _ => &word,
+                });
+            }
+            c => json.push(c),
+        }
+    }
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roast() -> RawRoastData {
+        let bean = (0..=600).step_by(5).map(|time| {
+            let time = time as f32;
+            (
+                if time < 90.0 {
+                    200.0 - time
+                } else {
+                    110.0 + (time - 90.0) * 0.2
+                },
+                time,
+            )
+        });
+        let exhaust = (0..=600)
+            .step_by(5)
+            .map(|time| (180.0 + time as f32 * 0.05, time as f32));
+        let start = batch::Start {
+            recipe: Some("House espresso".to_string()),
+            green_weight: 12.5,
+            operator: "Ana".to_string(),
+            machine: "Probat L12".to_string(),
+            started_at: Local.with_ymd_and_hms(2024, 5, 1, 9, 30, 15).unwrap(),
+            ..unknown_batch()
+        };
+        let end = batch::End {
+            roasted_weight: 10.5,
+            color: None,
+            notes: "Sweet, a bit flat".to_string(),
+        };
+
+        RawRoastData {
+            weight_loss: Some(batch::weight_loss(&start, &end)),
+            start,
+            end: Some(end),
+            events: vec![
+                Event {
+                    kind: EventKind::DryEnd,
+                    time: 240.0,
+                },
+                Event {
+                    kind: EventKind::FirstCrack,
+                    time: 480.0,
+                },
+            ],
+            data: vec![
+                curve(0, "Bean", COLORS[0], bean.collect()),
+                curve(1, "Exhaust", COLORS[1], exhaust.collect()),
+            ],
+            preheat: Vec::new(),
+        }
+    }
+
+    fn assert_points(actual: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual.0 - expected.0).abs() < 0.01,
+                "{actual:?} != {expected:?}"
+            );
+            assert!(
+                (actual.1 - expected.1).abs() < 0.01,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    fn assert_events(actual: &[Event], expected: &[Event]) {
+        let events = |events: &[Event]| -> Vec<(EventKind, f32)> {
+            events
+                .iter()
+                .map(|event| (event.kind, event.time))
+                .collect()
+        };
+        assert_eq!(events(actual), events(expected));
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let roast = roast();
+        let read = from_csv(&to_csv(&roast)).unwrap();
+
+        assert_eq!(read.data.len(), 2);
+        for (read, curve) in read.data.iter().zip(&roast.data) {
+            assert_eq!(read.name, curve.name);
+            assert_points(&read.points, &curve.points);
+        }
+        assert_ne!(read.data[0].color, read.data[1].color);
+        assert_events(&read.events, &roast.events);
+    }
+
+    #[test]
+    fn csv_header() {
+        assert!(from_csv("").is_err());
+        assert!(from_csv("seconds,bean\n0,200\n").is_err());
+        assert!(from_csv("time,event,Bean\nsoon,,200\n").is_err());
+    }
+
+    #[test]
+    fn artisan_round_trip() {
+        let roast = roast();
+        let read = from_artisan(&to_artisan(&roast)).unwrap();
+
+        for (read, curve) in read.data.iter().zip(&roast.data) {
+            assert_points(&read.points, &curve.points);
+        }
+        assert_events(&read.events, &roast.events);
+        assert_eq!(read.start.recipe, roast.start.recipe);
+        assert_eq!(read.start.operator, roast.start.operator);
+        assert_eq!(read.start.machine, roast.start.machine);
+        assert_eq!(read.start.started_at, roast.start.started_at);
+        assert_eq!(read.start.green_weight, roast.start.green_weight);
+        let (read_end, end) = (read.end.unwrap(), roast.end.unwrap());
+        assert_eq!(read_end.roasted_weight, end.roasted_weight);
+        assert_eq!(read_end.notes, end.notes);
+    }
+
+    #[test]
+    fn artisan_charge() {
+        let profile = "{'timex': [0.0, 30.0, 60.0, 90.0], 'temp1': [-1, 210.0, 205.0, 200.0], \
+                       'temp2': [180.0, 200.0, 150.0, 120.0], 'timeindex': [1, 3, 0, 0, 0, 0, 3, 0], \
+                       'weight': [1000.0, 850.0, 'g']}";
+        let read = from_artisan(profile).unwrap();
+
+        assert_points(
+            &read.data[0].points,
+            &[(200.0, 0.0), (150.0, 30.0), (120.0, 60.0)],
+        );
+        assert_points(
+            &read.data[1].points,
+            &[(210.0, 0.0), (205.0, 30.0), (200.0, 60.0)],
+        );
+        assert_events(
+            &read.events,
+            &[Event {
+                kind: EventKind::DryEnd,
+                time: 60.0,
+            }],
+        );
+        assert_eq!(read.start.green_weight, 1.0);
+        assert_eq!(read.end.unwrap().roasted_weight, 0.85);
+    }
+
+    #[test]
+    fn python_literals() {
+        assert_eq!(
+            python_to_json("{'a': (1, 2.5e-05), 'b': True, 'c': False, 'd': None}"),
+            r#"{"a": [1, 2.5e-05], "b": true, "c": false, "d": null}"#
+        );
+    }
+
+    #[test]
+    fn python_strings() {
+        assert_eq!(
+            python_to_json(r#"{'title': 'It\'s "light"', u'notes': u"None (True)"}"#),
+            r#"{"title": "It's \"light\"", "notes": "None (True)"}"#
+        );
+        assert_eq!(python_to_json(r"'a\\b\n'"), r#""a\\b\n""#);
+    }
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, Local};
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    Task,
+    widget::{
+        button, column, container, horizontal_space, pick_list, row, scrollable, slider, text,
+        text_input,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fmt, fs, io, path::PathBuf};
+
+use crate::{
+    archive::{self, Entry},
+    inventory::Lot,
+    preferences::PROJECT_DIRS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Attribute {
+    Fragrance,
+    Flavor,
+    Aftertaste,
+    Acidity,
+    Body,
+    Balance,
+    Uniformity,
+    CleanCup,
+    Sweetness,
+    Overall,
+}
+
+impl Attribute {
+    pub const ALL: [Attribute; 10] = [
+        Attribute::Fragrance,
+        Attribute::Flavor,
+        Attribute::Aftertaste,
+        Attribute::Acidity,
+        Attribute::Body,
+        Attribute::Balance,
+        Attribute::Uniformity,
+        Attribute::CleanCup,
+        Attribute::Sweetness,
+        Attribute::Overall,
+    ];
+
+    /// Uniformity, clean cup and sweetness are scored 2 points per cup,
+    /// the other attributes on the 6.00–10.00 quality scale.
+    fn per_cup(self) -> bool {
+        matches!(
+            self,
+            Attribute::Uniformity | Attribute::CleanCup | Attribute::Sweetness
+        )
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Attribute::Fragrance => "Fragrance/Aroma",
+            Attribute::Flavor => "Flavor",
+            Attribute::Aftertaste => "Aftertaste",
+            Attribute::Acidity => "Acidity",
+            Attribute::Body => "Body",
+            Attribute::Balance => "Balance",
+            Attribute::Uniformity => "Uniformity",
+            Attribute::CleanCup => "Clean cup",
+            Attribute::Sweetness => "Sweetness",
+            Attribute::Overall => "Overall",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cupping {
+    /// File name of the saved roast.
+    pub roast: String,
+    pub scores: BTreeMap<Attribute, f32>,
+    /// Defect points, taints (2) and faults (4) times the number of cups.
+    pub defects: f32,
+    pub notes: String,
+    pub cupped_at: DateTime<Local>,
+}
+
+impl Cupping {
+    pub fn total(&self) -> f32 {
+        self.scores.values().sum::<f32>() - self.defects
+    }
+}
+
+#[derive(Debug)]
+pub struct Cuppings {
+    cuppings: Vec<Cupping>,
+    roasts: Vec<Entry>,
+    selected: Option<String>,
+    scores: BTreeMap<Attribute, f32>,
+    defects: String,
+    notes: String,
+    error: Option<String>,
+    /// Why the cupping file could not be read, it is then never saved over.
+    load_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    RoastSelected(Entry),
+    ScoreChanged(Attribute, f32),
+    DefectsChanged(String),
+    NotesChanged(String),
+    Save,
+    Refresh,
+    Loaded(Vec<Entry>),
+}
+
+fn default_scores() -> BTreeMap<Attribute, f32> {
+    Attribute::ALL
+        .iter()
+        .map(|attribute| (*attribute, if attribute.per_cup() { 10.0 } else { 7.5 }))
+        .collect()
+}
+
+fn average(totals: impl Iterator<Item = f32>) -> Option<(f32, usize)> {
+    let (sum, count) = totals.fold((0.0, 0), |(sum, count), total| (sum + total, count + 1));
+    (count > 0).then(|| (sum / count as f32, count))
+}
+
+impl Cuppings {
+    pub fn new(cuppings: Vec<Cupping>) -> Self {
+        Cuppings {
+            cuppings,
+            roasts: Vec::new(),
+            selected: None,
+            scores: default_scores(),
+            defects: String::new(),
+            notes: String::new(),
+            error: None,
+            load_error: None,
+        }
+    }
+
+    fn data_file() -> PathBuf {
+        let mut path = PROJECT_DIRS.data_dir().join("_").to_path_buf();
+        path.set_file_name("cupping.json");
+        path
+    }
+
+    fn read() -> Result<Vec<Cupping>, Box<dyn Error>> {
+        match fs::read_to_string(Self::data_file()) {
+            Ok(string) => Ok(serde_json::from_str(&string)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Loads the cupping log, starting empty only when there is none yet.
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(cuppings) => Cuppings::new(cuppings),
+            Err(error) => Cuppings {
+                load_error: Some(format!(
+                    "{} could not be read, changes won't be saved: {}",
+                    Self::data_file().display(),
+                    error
+                )),
+                ..Cuppings::new(Vec::new())
+            },
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if self.load_error.is_some() {
+            return Err("The cupping log is left untouched as it could not be read".into());
+        }
+
+        let path = Self::data_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.cuppings)?)?;
+        Ok(())
+    }
+
+    /// Reads the saved roasts again, off the UI thread.
+    pub fn refresh(&self) -> Task<Message> {
+        Task::perform(archive::load_all(), Message::Loaded)
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::RoastSelected(entry) => self.selected = Some(entry.name),
+            Message::ScoreChanged(attribute, score) => {
+                self.scores.insert(attribute, score);
+            }
+            Message::DefectsChanged(defects) => self.defects = defects,
+            Message::NotesChanged(notes) => self.notes = notes,
+            Message::Save => {
+                let Some(roast) = self.selected.clone() else {
+                    self.error = Some("Select a roast first".to_string());
+                    return Task::none();
+                };
+                let defects = if self.defects.trim().is_empty() {
+                    0.0
+                } else if let Ok(defects) = self.defects.trim().parse() {
+                    defects
+                } else {
+                    self.error = Some("Defects must be a number".to_string());
+                    return Task::none();
+                };
+
+                self.cuppings.push(Cupping {
+                    roast,
+                    scores: self.scores.clone(),
+                    defects,
+                    notes: self.notes.trim().to_string(),
+                    cupped_at: Local::now(),
+                });
+                self.scores = default_scores();
+                self.defects.clear();
+                self.notes.clear();
+                self.error = self.save().err().map(|error| error.to_string());
+            }
+            Message::Refresh => return self.refresh(),
+            Message::Loaded(roasts) => self.roasts = roasts,
+        }
+
+        Task::none()
+    }
+
+    fn roast(&self, name: &str) -> Option<&Entry> {
+        self.roasts.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn view<'a>(&'a self, lots: &'a [Lot]) -> Element<'a, Message> {
+        let title = column![text("Cupping").size(30)].push_maybe(
+            self.load_error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        );
+
+        let selected = self.selected.as_deref().and_then(|name| self.roast(name));
+        let header = row![
+            pick_list(self.roasts.as_slice(), selected, Message::RoastSelected)
+                .placeholder("Select a roast...")
+                .width(Fill),
+            button("Refresh")
+                .on_press(Message::Refresh)
+                .style(button::secondary),
+        ]
+        .spacing(10);
+
+        let form = column(Attribute::ALL.iter().map(|attribute| {
+            let attribute = *attribute;
+            let score = self.scores.get(&attribute).copied().unwrap_or_default();
+            let (range, step) = if attribute.per_cup() {
+                (0.0..=10.0, 2.0)
+            } else {
+                (6.0..=10.0, 0.25)
+            };
+
+            row![
+                text(attribute.to_string()).width(150),
+                slider(range, score, move |score| Message::ScoreChanged(
+                    attribute, score
+                ))
+                .step(step),
+                text(format!("{:.2}", score)).width(50),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        }))
+        .spacing(5);
+
+        let total = self.scores.values().sum::<f32>()
+            - self.defects.trim().parse::<f32>().unwrap_or_default();
+
+        let form = column![
+            form,
+            row![
+                text("Defects").width(150),
+                text_input("0", &self.defects).on_input(Message::DefectsChanged),
+            ]
+            .align_y(Alignment::Center),
+            text_input("Notes", &self.notes).on_input(Message::NotesChanged),
+            row![
+                text(format!("Total: {:.2}", total)).size(20),
+                horizontal_space(),
+                button("Save cupping")
+                    .on_press(Message::Save)
+                    .style(button::success),
+            ]
+            .align_y(Alignment::Center),
+        ]
+        .push_maybe(
+            self.error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10);
+
+        let mut by_recipe: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+        let mut by_lot: BTreeMap<u64, Vec<f32>> = BTreeMap::new();
+        for cupping in &self.cuppings {
+            if let Some(entry) = self.roast(&cupping.roast) {
+                if let Some(recipe) = &entry.roast.start.recipe {
+                    by_recipe
+                        .entry(recipe.clone())
+                        .or_default()
+                        .push(cupping.total());
+                }
+                if let Some(lot) = entry.roast.start.lot {
+                    by_lot.entry(lot).or_default().push(cupping.total());
+                }
+            }
+        }
+
+        let averages = column![text("Averages per recipe:").size(20)]
+            .extend(by_recipe.into_iter().filter_map(|(recipe, totals)| {
+                let (average, count) = average(totals.into_iter())?;
+                Some(text(format!("{}: {:.2} ({} cuppings)", recipe, average, count)).into())
+            }))
+            .push(text("Averages per green lot:").size(20))
+            .extend(by_lot.into_iter().filter_map(|(id, totals)| {
+                let (average, count) = average(totals.into_iter())?;
+                let lot = lots
+                    .iter()
+                    .find(|lot| lot.id == id)
+                    .map_or(format!("#{}", id), |lot| {
+                        format!("#{} {} – {}", lot.id, lot.origin, lot.farm)
+                    });
+                Some(text(format!("{}: {:.2} ({} cuppings)", lot, average, count)).into())
+            }))
+            .spacing(5);
+
+        container(scrollable(
+            column![title, header, form, averages]
+                .max_width(800)
+                .spacing(20),
+        ))
+        .center_x(Fill)
+        .padding(20)
+        .into()
+    }
+}
@@ -3,9 +3,10 @@ use iced::{
     widget::{container, row, text},
 };
 use once_cell::sync::Lazy;
-use std::{fmt, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, fs, path::Path, time::Duration};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Recipe {
     name: String,
     steps: Vec<Step>,
@@ -25,14 +26,83 @@ impl Recipe {
     pub fn steps(&self) -> &Vec<Step> {
         &self.steps
     }
+
+    /// Reads a recipe from a JSON or TOML file.
+    pub fn load(path: &Path) -> Result<Recipe, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(toml::from_str(&string)?),
+            _ => Ok(serde_json::from_str(&string)?),
+        }
+    }
+
+    /// Lists what is wrong with the recipe, if anything.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push("the recipe has no name".to_string());
+        }
+        if !matches!(self.steps.first(), Some(step) if matches!(step.step_type, StepType::Start)) {
+            problems.push("the first step must be Start".to_string());
+        }
+        if !matches!(self.steps.last(), Some(step) if matches!(step.step_type, StepType::End)) {
+            problems.push("the last step must be End".to_string());
+        }
+
+        for (i, pair) in self.steps.windows(2).enumerate() {
+            let decreasing = match (&pair[0].checkpoint, &pair[1].checkpoint) {
+                (Checkpoint::Time(a), Checkpoint::Time(b)) => b < a,
+                (Checkpoint::Temp(a), Checkpoint::Temp(b)) => b < a,
+                _ => false,
+            };
+            // The start temperature is the charge, the curve drops below it
+            // before rising again.
+            if decreasing && i > 0 {
+                problems.push(format!("step {} comes before the previous one", i + 2));
+            }
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let valid = match &step.step_type {
+                StepType::AdjustAirflow(value) | StepType::AdjustGas(value) => {
+                    (0.0..=1.0).contains(value)
+                }
+                StepType::DeltaTempOnOffGas(delta) => *delta > 0.0,
+                _ => true,
+            };
+            if !valid {
+                problems.push(format!("step {} has an out of range value", i + 1));
+            }
+            if let Checkpoint::Temp(temp) = step.checkpoint {
+                if !(0.0..=300.0).contains(&temp) {
+                    problems.push(format!("step {} has an unrealistic temperature", i + 1));
+                }
+            }
+        }
+
+        problems
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Checkpoint {
     Time(Duration),
     Temp(f32),
 }
 
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Checkpoint::Time(duration) => {
+                let secs = duration.as_secs();
+                write!(f, "{}:{:02}", secs / 60, secs % 60)
+            }
+            Checkpoint::Temp(temp) => write!(f, "{} °C", temp),
+        }
+    }
+}
+
 impl Checkpoint {
     pub fn view<'a, Message>(&self) -> Element<'a, Message> {
         match self {
@@ -46,7 +116,7 @@ impl Checkpoint {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum StepType {
     Start,
     End,
@@ -118,13 +188,49 @@ impl StepType {
     }
 }
 
-#[derive(Clone, Debug)]
+impl fmt::Display for StepType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepType::Start => write!(f, "Start"),
+            StepType::End => write!(f, "End"),
+            StepType::AdjustAirflow(airflow) => write!(f, "Airflow to {}", airflow),
+            StepType::SwitchGas(gas) => write!(f, "Gas {}", if *gas { "ON" } else { "OFF" }),
+            StepType::AdjustGas(gas) => write!(f, "Gas to {}", gas),
+            StepType::DurationOnOffGas(duration) => {
+                write!(f, "Gas on/off every {} secs", duration.as_secs())
+            }
+            StepType::DeltaTempOnOffGas(delta) => write!(f, "Gas on/off every {} °C", delta),
+            StepType::SwitchCooling(cooling) => {
+                write!(f, "Cooling {}", if *cooling { "ON" } else { "OFF" })
+            }
+            StepType::SwitchMixing(mixing) => {
+                write!(f, "Mixing {}", if *mixing { "ON" } else { "OFF" })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Step {
     checkpoint: Checkpoint,
     step_type: StepType,
 }
 
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.step_type, self.checkpoint)
+    }
+}
+
 impl Step {
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    pub fn step_type(&self) -> &StepType {
+        &self.step_type
+    }
+
     pub fn view<'a, Message: 'a>(&'a self) -> Element<'a, Message> {
         row![self.checkpoint.view(), self.step_type.view()]
             .height(35)
@@ -246,6 +352,10 @@ pub fn t_switch_mixing(time: (u64, u64), to: bool) -> Step {
     }
 }
 
+pub fn recipes() -> Vec<Recipe> {
+    vec![DUMB_RECIPE.clone(), TIME_RECIPE.clone()]
+}
+
 pub static DUMB_RECIPE: Lazy<Recipe> = Lazy::new(|| Recipe {
     name: "Nicaragua".to_string(),
     steps: vec![
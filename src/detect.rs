@@ -0,0 +1,151 @@
+//! The charge and the drop show on the bean probe as a sudden fall of its
+//! temperature: cold beans poured on the hot probe, or the probe left in the
+//! cooler air once the beans leave the drum.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::sensor::TempData;
+
+/// A fall of at least `FALL` °C within `WINDOW`.
+const FALL: f64 = 15.0;
+const WINDOW: Duration = Duration::from_secs(15);
+/// The fall of the charge is over by then.
+const MIN_ROAST: Duration = Duration::from_secs(180);
+/// Readings this close to the highest one before the fall are still on its
+/// plateau.
+const PLATEAU: f64 = 1.0;
+
+/// When the first sudden fall after `from` began: the last reading on the
+/// plateau before it.
+pub fn fall(points: &[TempData], from: Instant) -> Option<Instant> {
+    let points = &points[points.partition_point(|temp_data| temp_data.time < from)..];
+    let mut first = 0;
+    // Indices of the window in decreasing temperature, its highest first,
+    // so that every reading is pushed and popped once.
+    let mut highest: VecDeque<usize> = VecDeque::new();
+
+    points.iter().enumerate().find_map(|(i, temp_data)| {
+        while temp_data.time.duration_since(points[first].time) > WINDOW {
+            first += 1;
+        }
+        while highest.front().is_some_and(|&j| j < first) {
+            highest.pop_front();
+        }
+
+        let fall = highest.front().and_then(|&j| {
+            let highest = points[j].temp;
+            (highest - temp_data.temp >= FALL).then(|| {
+                points[first..i]
+                    .iter()
+                    .rev()
+                    .find(|temp_data| temp_data.temp >= highest - PLATEAU)
+                    .map_or(temp_data.time, |temp_data| temp_data.time)
+            })
+        });
+
+        while highest
+            .back()
+            .is_some_and(|&j| points[j].temp <= temp_data.temp)
+        {
+            highest.pop_back();
+        }
+        highest.push_back(i);
+
+        fall
+    })
+}
+
+/// When the beans left the drum, looked for from `from` but no sooner than
+/// a few minutes after charge.
+pub fn drop_time(points: &[TempData], charge: Instant, from: Instant) -> Option<Instant> {
+    fall(points, from.max(charge + MIN_ROAST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One reading per second.
+    fn series(temps: impl IntoIterator<Item = f64>) -> (Instant, Vec<TempData>) {
+        let start = Instant::now();
+        let points = temps
+            .into_iter()
+            .enumerate()
+            .map(|(i, temp)| TempData {
+                temp,
+                time: start + Duration::from_secs(i as u64),
+            })
+            .collect();
+        (start, points)
+    }
+
+    fn at(start: Instant, seconds: u64) -> Option<Instant> {
+        Some(start + Duration::from_secs(seconds))
+    }
+
+    #[test]
+    fn charge_dip() {
+        let plateau = (0..=60).map(|_| 200.0);
+        let charge = (1..=40).map(|i| 200.0 - 3.0 * i as f64);
+        let (start, points) = series(plateau.chain(charge));
+
+        assert_eq!(fall(&points, start), at(start, 60));
+    }
+
+    #[test]
+    fn no_fall() {
+        let (start, points) = series((0..300).map(|i| 100.0 + i as f64 * 0.3));
+
+        assert_eq!(fall(&points, start), None);
+    }
+
+    #[test]
+    fn turning_point_is_not_a_drop() {
+        let charge = (0..=90).map(|i| 200.0 - i as f64);
+        let roast = (1..=510).map(|i| 110.0 + i as f64 * 0.2);
+        let (start, mut points) = series(charge.chain(roast));
+
+        assert_eq!(drop_time(&points, start, start), None);
+
+        let last = points.last().unwrap().clone();
+        points.extend((1..=20).map(|i| TempData {
+            temp: last.temp - 3.0 * i as f64,
+            time: last.time + Duration::from_secs(i),
+        }));
+
+        assert_eq!(drop_time(&points, start, start), at(start, 600));
+    }
+
+    #[test]
+    fn drop_after_resume() {
+        let roast = (0..=300).map(|i| 100.0 + i as f64 * 0.3);
+        let dip = (1..=20).map(|i| 190.0 - 2.0 * i as f64);
+        let recovery = (1..=100).map(|i| 150.0 + i as f64 * 0.5);
+        let (start, points) = series(roast.chain(dip).chain(recovery));
+
+        assert_eq!(drop_time(&points, start, start), at(start, 300));
+        assert_eq!(
+            drop_time(&points, start, start + Duration::from_secs(330)),
+            None
+        );
+    }
+
+    #[test]
+    fn noisy_plateau() {
+        let plateau = (0..=60).map(|i| if i % 2 == 0 { 199.5 } else { 200.5 });
+        let charge = (1..=30).map(|i| 199.5 - 2.0 * i as f64);
+        let (start, points) = series(plateau.chain(charge));
+
+        assert_eq!(fall(&points, start), at(start, 60));
+    }
+
+    #[test]
+    fn noise_is_not_a_fall() {
+        let (start, points) = series((0..300).map(|i| 200.0 + if i % 7 == 0 { -6.0 } else { 0.0 }));
+
+        assert_eq!(fall(&points, start), None);
+    }
+}
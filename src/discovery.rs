@@ -0,0 +1,261 @@
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    Task, task,
+    widget::{button, column, horizontal_space, row, text},
+};
+use std::collections::BTreeMap;
+
+use crate::{
+    filter,
+    sensor::{self, Device, DeviceKind, Discovery as Found, TempData},
+};
+
+pub const COLORS: [[f32; 3]; 6] = [
+    [0., 0.5, 1.],
+    [1., 0., 0.],
+    [0., 0.8, 0.3],
+    [1., 0.6, 0.],
+    [0.6, 0.3, 1.],
+    [1., 0.2, 0.7],
+];
+
+#[derive(Debug, Default)]
+pub struct Discovery {
+    devices: Vec<Probe>,
+    scan: Option<task::Handle>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Probe {
+    device: Device,
+    reading: Option<TempData>,
+    connection: Option<task::Handle>,
+    in_use: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scan,
+    Stop,
+    Found(Found),
+    Finished(Result<(), sensor::Error>),
+    Reading(Device, sensor::Event),
+    ProbeClosed(Device),
+    Add(Device),
+}
+
+impl Discovery {
+    /// Handles a message, returning a sensor to add to the configuration
+    /// when the user picked one.
+    pub fn update(
+        &mut self,
+        message: Message,
+        configured: &[sensor::Config],
+    ) -> (Task<Message>, Option<sensor::Config>) {
+        match message {
+            Message::Scan => {
+                self.devices.clear();
+                self.error = None;
+
+                let (task, handle) =
+                    Task::sip(sensor::discover(), Message::Found, Message::Finished).abortable();
+                self.scan = Some(handle.abort_on_drop());
+
+                (task, None)
+            }
+            Message::Stop | Message::Finished(_) => {
+                if let Message::Finished(Err(error)) = message {
+                    self.error = Some(format!("The scan failed: {}", error));
+                }
+                self.scan = None;
+                self.devices.clear();
+                (Task::none(), None)
+            }
+            Message::Found(Found::Attached(device)) => {
+                let in_use = configured.iter().any(|config| device.matches(config));
+
+                let task = if device.kind == DeviceKind::Temperature && !in_use {
+                    let probe = device.clone();
+                    let closed = device.clone();
+                    let (task, handle) = Task::sip(
+                        sensor::connect_temperature(
+                            device.hub_port,
+                            device.serial_number,
+                            device.channel,
+                        ),
+                        move |event| Message::Reading(probe.clone(), event),
+                        move |_| Message::ProbeClosed(closed.clone()),
+                    )
+                    .abortable();
+
+                    self.devices.push(Probe {
+                        device,
+                        reading: None,
+                        connection: Some(handle.abort_on_drop()),
+                        in_use,
+                    });
+                    task
+                } else {
+                    self.devices.push(Probe {
+                        device,
+                        reading: None,
+                        connection: None,
+                        in_use,
+                    });
+                    Task::none()
+                };
+
+                (task, None)
+            }
+            Message::Found(Found::Detached(device)) => {
+                self.devices.retain(|probe| probe.device != device);
+                (Task::none(), None)
+            }
+            Message::Reading(device, event) => {
+                if let (Some(probe), sensor::Event::Change(temp_data)) = (
+                    self.devices.iter_mut().find(|probe| probe.device == device),
+                    event,
+                ) {
+                    probe.reading = Some(temp_data);
+                }
+                (Task::none(), None)
+            }
+            Message::ProbeClosed(device) => {
+                if let Some(probe) = self.devices.iter_mut().find(|probe| probe.device == device) {
+                    probe.connection = None;
+                    probe.reading = None;
+                }
+                (Task::none(), None)
+            }
+            Message::Add(device) => {
+                // The channel can only be opened once, release it for the
+                // roasting screen.
+                self.devices.retain(|probe| probe.device != device);
+
+                let config = sensor::Config {
+                    name: format!("{} #{}", device.name, device.channel),
+                    color: COLORS[configured.len() % COLORS.len()],
+                    hub_port: device.hub_port,
+                    serial_number: device.serial_number,
+                    channel: device.channel,
+                    filter: filter::Settings::default(),
+                    source: sensor::Source::Phidget,
+                    machine: String::new(),
+                };
+
+                (Task::none(), Some(config))
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let action = if self.scan.is_some() {
+            button("Stop").on_press(Message::Stop)
+        } else {
+            button("Scan for devices").on_press(Message::Scan)
+        };
+
+        let mut hubs: Vec<&Probe> = self
+            .devices
+            .iter()
+            .filter(|probe| probe.device.kind == DeviceKind::Hub)
+            .collect();
+        hubs.sort_by_key(|probe| probe.device.serial_number);
+
+        let mut channels: BTreeMap<i32, Vec<&Probe>> = BTreeMap::new();
+        for probe in self
+            .devices
+            .iter()
+            .filter(|probe| probe.device.kind != DeviceKind::Hub)
+        {
+            channels
+                .entry(probe.device.serial_number)
+                .or_default()
+                .push(probe);
+        }
+
+        // Channels of devices plugged straight into USB have no hub to be
+        // listed under, they are grouped by the serial number of their device.
+        let mut groups: Vec<(String, Vec<&Probe>)> = hubs
+            .into_iter()
+            .map(|hub| {
+                (
+                    format!("{} ({})", hub.device.name, hub.device.serial_number),
+                    channels
+                        .remove(&hub.device.serial_number)
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+        groups.extend(
+            channels
+                .into_iter()
+                .map(|(serial_number, channels)| (format!("Device {serial_number}"), channels)),
+        );
+
+        let hubs = column(groups.into_iter().map(|(title, mut channels)| {
+            channels.sort_by_key(|probe| (probe.device.hub_port, probe.device.channel));
+
+            column![text(title)]
+                .extend(channels.into_iter().map(|probe| probe.view()))
+                .spacing(5)
+                .into()
+        }))
+        .spacing(15);
+
+        column![
+            row![text("Devices:").width(Fill), action].align_y(Alignment::Center),
+            hubs
+        ]
+        .push_maybe(
+            self.error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10)
+        .into()
+    }
+}
+
+impl Probe {
+    fn view(&self) -> Element<Message> {
+        let device = &self.device;
+        let label = text(format!(
+            "Port {} / Channel {}: {}",
+            device.hub_port, device.channel, device.name
+        ))
+        .size(14);
+
+        let details: Element<Message> = match device.kind {
+            DeviceKind::Temperature if self.in_use => {
+                text("In use").size(14).style(text::secondary).into()
+            }
+            DeviceKind::Temperature => {
+                let reading = match (&self.reading, &self.connection) {
+                    (Some(temp_data), _) => text(format!("{:.1} °C", temp_data.temp)),
+                    (None, Some(_)) => text("Loading...").style(text::base),
+                    (None, None) => text("No reading").style(text::secondary),
+                };
+
+                row![
+                    reading.size(14),
+                    button(text("Add as sensor").size(14))
+                        .on_press(Message::Add(device.clone()))
+                        .style(button::success)
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .into()
+            }
+            DeviceKind::VintPort => text("VINT port").size(14).style(text::secondary).into(),
+            DeviceKind::Hub | DeviceKind::Other => horizontal_space().width(0).into(),
+        };
+
+        row![label, horizontal_space(), details]
+            .padding([0, 20])
+            .align_y(Alignment::Center)
+            .into()
+    }
+}
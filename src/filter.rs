@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::sensor::TempData;
+
+pub const MEDIAN_WINDOWS: [usize; 4] = [1, 3, 5, 7];
+pub const DECIMATIONS: [Decimation; 4] = [
+    Decimation(0),
+    Decimation(250),
+    Decimation(500),
+    Decimation(1000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decimation(pub u64);
+
+impl fmt::Display for Decimation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => write!(f, "Every sample"),
+            ms => write!(f, "{} ms", ms),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Number of samples in the median window, `1` disables spike rejection.
+    pub median: usize,
+    /// Weight of the newest sample in the exponential smoothing, `1.0`
+    /// disables smoothing.
+    pub smoothing: f64,
+    /// Minimum interval between two output samples.
+    pub decimation: Decimation,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            median: 3,
+            smoothing: 0.5,
+            decimation: Decimation(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    settings: Settings,
+    window: VecDeque<f64>,
+    smoothed: Option<f64>,
+    last_output: Option<Instant>,
+}
+
+impl Filter {
+    pub fn new(settings: Settings) -> Self {
+        Filter {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Changes the settings, keeping the samples seen so far so the output
+    /// doesn't jump.
+    pub fn set_settings(&mut self, settings: Settings) {
+        let median = settings.median.max(1);
+        while self.window.len() > median {
+            self.window.pop_front();
+        }
+        self.settings = settings;
+    }
+
+    /// Feeds a raw sample through the pipeline, returning the filtered
+    /// sample unless it was dropped by the decimation.
+    pub fn apply(&mut self, temp_data: &TempData) -> Option<TempData> {
+        let median = self.settings.median.max(1);
+        if self.window.len() == median {
+            self.window.pop_front();
+        }
+        self.window.push_back(temp_data.temp);
+
+        let mut sorted: Vec<f64> = self.window.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let temp = sorted[sorted.len() / 2];
+
+        let alpha = self.settings.smoothing.clamp(0.05, 1.0);
+        let temp = match self.smoothed {
+            Some(smoothed) => alpha * temp + (1.0 - alpha) * smoothed,
+            None => temp,
+        };
+        self.smoothed = Some(temp);
+
+        let interval = Duration::from_millis(self.settings.decimation.0);
+        if self
+            .last_output
+            .is_some_and(|last| temp_data.time.duration_since(last) < interval)
+        {
+            return None;
+        }
+        self.last_output = Some(temp_data.time);
+
+        Some(TempData {
+            temp,
+            time: temp_data.time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(median: usize, smoothing: f64, decimation: u64) -> Settings {
+        Settings {
+            median,
+            smoothing,
+            decimation: Decimation(decimation),
+        }
+    }
+
+    /// Feeds one sample every 100 ms, returning the output temperatures.
+    fn run(filter: &mut Filter, temps: &[f64]) -> Vec<Option<f64>> {
+        let start = Instant::now();
+        temps
+            .iter()
+            .enumerate()
+            .map(|(i, temp)| {
+                filter
+                    .apply(&TempData {
+                        temp: *temp,
+                        time: start + Duration::from_millis(100 * i as u64),
+                    })
+                    .map(|temp_data| temp_data.temp)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pass_through() {
+        let mut filter = Filter::new(settings(1, 1.0, 0));
+
+        assert_eq!(
+            run(&mut filter, &[1.0, 5.0, 3.0]),
+            [Some(1.0), Some(5.0), Some(3.0)]
+        );
+    }
+
+    #[test]
+    fn median_rejects_a_spike() {
+        let mut filter = Filter::new(settings(3, 1.0, 0));
+
+        assert_eq!(
+            run(&mut filter, &[200.0, 200.0, 400.0, 201.0]),
+            [Some(200.0), Some(200.0), Some(200.0), Some(201.0)]
+        );
+    }
+
+    #[test]
+    fn smoothing() {
+        let mut filter = Filter::new(settings(1, 0.5, 0));
+
+        assert_eq!(
+            run(&mut filter, &[100.0, 200.0, 200.0]),
+            [Some(100.0), Some(150.0), Some(175.0)]
+        );
+    }
+
+    #[test]
+    fn decimation() {
+        let mut filter = Filter::new(settings(1, 1.0, 250));
+        let outputs = run(&mut filter, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        assert_eq!(outputs, [Some(1.0), None, None, Some(4.0), None, None]);
+    }
+
+    #[test]
+    fn changing_settings_keeps_the_state() {
+        let mut filter = Filter::new(settings(5, 1.0, 0));
+        run(&mut filter, &[100.0; 5]);
+        filter.set_settings(settings(3, 0.5, 0));
+
+        assert_eq!(filter.window.len(), 3);
+        assert_eq!(run(&mut filter, &[200.0]), [Some(100.0)]);
+        assert_eq!(filter.settings(), &settings(3, 0.5, 0));
+    }
+}
@@ -0,0 +1,34 @@
+//! How times and temperatures are written in the views, the reports and on
+//! the command line.
+
+/// `minutes:seconds`, rounded to the second.
+pub fn duration(seconds: f32) -> String {
+    let seconds = seconds.max(0.0).round() as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// A `(seconds, temp)` point of the roast, or a dash if it never happened.
+pub fn point(point: Option<(f32, f32)>) -> String {
+    point.map_or("–".to_string(), |(time, temp)| {
+        format!("{} @ {:.1} °C", duration(time), temp)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(0.0), "0:00");
+        assert_eq!(duration(59.6), "1:00");
+        assert_eq!(duration(605.0), "10:05");
+        assert_eq!(duration(-3.0), "0:00");
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(point(Some((90.0, 201.25))), "1:30 @ 201.2 °C");
+        assert_eq!(point(None), "–");
+    }
+}
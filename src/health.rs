@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::sensor::TempData;
+
+const WINDOW: usize = 20;
+const SPIKE_MEMORY: Duration = Duration::from_secs(30);
+
+const STALE_AFTER: Duration = Duration::from_secs(2);
+const SPIKE_DELTA: f64 = 15.0;
+const MAX_RECENT_SPIKES: usize = 3;
+const MAX_NOISE: f64 = 1.5;
+
+#[derive(Debug, Clone, Default)]
+pub struct Health {
+    recent: VecDeque<TempData>,
+    spike_times: VecDeque<Instant>,
+    first_time: Option<Instant>,
+    last_time: Option<Instant>,
+    max_gap: Duration,
+    samples: usize,
+    spikes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Unknown,
+    Healthy,
+    Degraded(Issue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    Stale(Duration),
+    Spiking(usize),
+    Noisy(f64),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Stale(duration) => write!(f, "no reading for {:.0} s", duration.as_secs_f32()),
+            Issue::Spiking(count) => write!(f, "{} spikes", count),
+            Issue::Noisy(noise) => write!(f, "noisy (±{:.1} °C)", noise),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Report {
+    samples: usize,
+    spikes: usize,
+    sample_rate: Option<f64>,
+    max_gap: f32,
+    noise: Option<f64>,
+    degraded: bool,
+}
+
+impl Health {
+    /// Records a new reading and returns `true` if it looks like a spike.
+    pub fn record(&mut self, temp_data: &TempData) -> bool {
+        self.samples += 1;
+
+        if let Some(last_time) = self.last_time {
+            self.max_gap = self.max_gap.max(temp_data.time.duration_since(last_time));
+        }
+        self.first_time.get_or_insert(temp_data.time);
+        self.last_time = Some(temp_data.time);
+
+        while self
+            .spike_times
+            .front()
+            .is_some_and(|t| temp_data.time.duration_since(*t) > SPIKE_MEMORY)
+        {
+            self.spike_times.pop_front();
+        }
+
+        let spike = self
+            .median()
+            .is_some_and(|median| (temp_data.temp - median).abs() > SPIKE_DELTA);
+
+        if spike {
+            self.spikes += 1;
+            self.spike_times.push_back(temp_data.time);
+        } else {
+            if self.recent.len() == WINDOW {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(temp_data.clone());
+        }
+
+        spike
+    }
+
+    fn median(&self) -> Option<f64> {
+        if self.recent.len() < 3 {
+            return None;
+        }
+
+        let mut temps: Vec<f64> = self.recent.iter().map(|td| td.temp).collect();
+        temps.sort_by(f64::total_cmp);
+        Some(temps[temps.len() / 2])
+    }
+
+    /// Samples per second over the recent window.
+    pub fn sample_rate(&self) -> Option<f64> {
+        let first = self.recent.front()?;
+        let last = self.recent.back()?;
+        let span = last.time.duration_since(first.time).as_secs_f64();
+
+        (span > 0.0).then(|| (self.recent.len() - 1) as f64 / span)
+    }
+
+    pub fn since_last(&self, now: Instant) -> Option<Duration> {
+        self.last_time.map(|t| now.saturating_duration_since(t))
+    }
+
+    /// Standard deviation of the sample-to-sample differences around their
+    /// mean, which ignores the slow trend of a roast.
+    pub fn noise(&self) -> Option<f64> {
+        if self.recent.len() < 3 {
+            return None;
+        }
+
+        let diffs: Vec<f64> = self
+            .recent
+            .iter()
+            .zip(self.recent.iter().skip(1))
+            .map(|(a, b)| b.temp - a.temp)
+            .collect();
+        let mean = diffs.iter().sum::<f64>() / diffs.len() as f64;
+        let variance = diffs.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / diffs.len() as f64;
+
+        Some((variance / 2.0).sqrt())
+    }
+
+    pub fn status(&self, now: Instant) -> Status {
+        let Some(since_last) = self.since_last(now) else {
+            return Status::Unknown;
+        };
+
+        if since_last > STALE_AFTER {
+            Status::Degraded(Issue::Stale(since_last))
+        } else if self.spike_times.len() >= MAX_RECENT_SPIKES {
+            Status::Degraded(Issue::Spiking(self.spike_times.len()))
+        } else if let Some(noise) = self.noise().filter(|noise| *noise > MAX_NOISE) {
+            Status::Degraded(Issue::Noisy(noise))
+        } else {
+            Status::Healthy
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        let sample_rate = match (self.first_time, self.last_time) {
+            (Some(first), Some(last)) if last > first => {
+                Some((self.samples - 1) as f64 / last.duration_since(first).as_secs_f64())
+            }
+            _ => None,
+        };
+        let noise = self.noise();
+
+        Report {
+            samples: self.samples,
+            spikes: self.spikes,
+            sample_rate,
+            max_gap: self.max_gap.as_secs_f32(),
+            noise,
+            degraded: self.max_gap > STALE_AFTER
+                || self.spikes >= MAX_RECENT_SPIKES
+                || noise.is_some_and(|noise| noise > MAX_NOISE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records one reading per second, returning the spikes.
+    fn record(health: &mut Health, start: Instant, temps: &[f64]) -> Vec<bool> {
+        let offset = health.samples;
+        temps
+            .iter()
+            .enumerate()
+            .map(|(i, temp)| {
+                health.record(&TempData {
+                    temp: *temp,
+                    time: start + Duration::from_secs((offset + i) as u64),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_readings_are_not_spikes() {
+        let mut health = Health::default();
+
+        assert_eq!(
+            record(&mut health, Instant::now(), &[20.0, 200.0, 20.0]),
+            [false; 3]
+        );
+    }
+
+    #[test]
+    fn spikes_are_kept_out_of_the_median() {
+        let mut health = Health::default();
+        let start = Instant::now();
+        record(&mut health, start, &[200.0; 10]);
+
+        assert_eq!(record(&mut health, start, &[230.0, 201.0]), [true, false]);
+        assert_eq!(health.median(), Some(200.0));
+        assert_eq!(health.spikes, 1);
+    }
+
+    #[test]
+    fn trend_is_not_noise() {
+        let mut health = Health::default();
+        let temps: Vec<f64> = (0..20).map(|i| 100.0 + i as f64 * 0.5).collect();
+        record(&mut health, Instant::now(), &temps);
+
+        assert!(health.noise().is_some_and(|noise| noise < 1e-9));
+        assert_eq!(health.sample_rate(), Some(1.0));
+    }
+
+    #[test]
+    fn statuses() {
+        let mut health = Health::default();
+        let start = Instant::now();
+        assert_eq!(health.status(start), Status::Unknown);
+
+        record(&mut health, start, &[200.0; 10]);
+        let last = start + Duration::from_secs(9);
+        assert_eq!(health.status(last), Status::Healthy);
+        assert_eq!(
+            health.status(last + Duration::from_secs(5)),
+            Status::Degraded(Issue::Stale(Duration::from_secs(5)))
+        );
+
+        record(&mut health, start, &[250.0, 250.0, 250.0]);
+        assert_eq!(
+            health.status(start + Duration::from_secs(12)),
+            Status::Degraded(Issue::Spiking(3))
+        );
+        assert!(health.report().degraded);
+    }
+
+    #[test]
+    fn noisy() {
+        let mut health = Health::default();
+        let start = Instant::now();
+        let temps: Vec<f64> = (0..20)
+            .map(|i| if i % 2 == 0 { 198.0 } else { 202.0 })
+            .collect();
+        record(&mut health, start, &temps);
+
+        let noise = health.noise().unwrap();
+        assert!((noise - 8f64.sqrt()).abs() < 0.1, "noise {}", noise);
+        assert!(matches!(
+            health.status(start + Duration::from_secs(19)),
+            Status::Degraded(Issue::Noisy(_))
+        ));
+    }
+}
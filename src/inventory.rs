@@ -0,0 +1,438 @@
+use chrono::NaiveDate;
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    widget::{button, column, container, horizontal_space, row, scrollable, text, text_input},
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, fs, io, path::PathBuf};
+
+use crate::preferences::PROJECT_DIRS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lot {
+    pub id: u64,
+    pub origin: String,
+    pub farm: String,
+    pub process: String,
+    /// Moisture content in percent.
+    pub moisture: f32,
+    /// Density in g/L.
+    pub density: f32,
+    pub supplier: String,
+    pub purchase_date: NaiveDate,
+    pub stock_kg: f32,
+}
+
+impl fmt::Display for Lot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} – {} ({:.2} kg)",
+            self.id, self.origin, self.farm, self.stock_kg
+        )
+    }
+}
+
+/// The inventory file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Stock {
+    /// Id of the next new lot. Ids of deleted lots are never given again,
+    /// saved roasts and cuppings still refer to them.
+    next_id: u64,
+    lots: Vec<Lot>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum File {
+    Stock(Stock),
+    /// Written before the ids were counted.
+    Lots(Vec<Lot>),
+}
+
+impl Default for Stock {
+    fn default() -> Self {
+        Stock {
+            next_id: 1,
+            lots: Vec::new(),
+        }
+    }
+}
+
+impl Stock {
+    fn parse(string: &str) -> Result<Self, serde_json::Error> {
+        let stock = match serde_json::from_str(string)? {
+            File::Stock(stock) => stock,
+            File::Lots(lots) => Stock { next_id: 0, lots },
+        };
+        let after_last = stock.lots.iter().map(|lot| lot.id + 1).max().unwrap_or(1);
+
+        Ok(Stock {
+            next_id: stock.next_id.max(after_last),
+            ..stock
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Inventory {
+    lots: Vec<Lot>,
+    next_id: u64,
+    form: Form,
+    editing: Option<u64>,
+    error: Option<String>,
+    /// Why the inventory file could not be read, it is then never saved over.
+    load_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Form {
+    origin: String,
+    farm: String,
+    process: String,
+    moisture: String,
+    density: String,
+    supplier: String,
+    purchase_date: String,
+    stock_kg: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    Origin,
+    Farm,
+    Process,
+    Moisture,
+    Density,
+    Supplier,
+    PurchaseDate,
+    Stock,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FieldChanged(Field, String),
+    Save,
+    Edit(u64),
+    Cancel,
+    Delete(u64),
+}
+
+impl Form {
+    fn field_mut(&mut self, field: Field) -> &mut String {
+        match field {
+            Field::Origin => &mut self.origin,
+            Field::Farm => &mut self.farm,
+            Field::Process => &mut self.process,
+            Field::Moisture => &mut self.moisture,
+            Field::Density => &mut self.density,
+            Field::Supplier => &mut self.supplier,
+            Field::PurchaseDate => &mut self.purchase_date,
+            Field::Stock => &mut self.stock_kg,
+        }
+    }
+
+    fn from_lot(lot: &Lot) -> Self {
+        Form {
+            origin: lot.origin.clone(),
+            farm: lot.farm.clone(),
+            process: lot.process.clone(),
+            moisture: lot.moisture.to_string(),
+            density: lot.density.to_string(),
+            supplier: lot.supplier.clone(),
+            purchase_date: lot.purchase_date.format("%Y-%m-%d").to_string(),
+            stock_kg: lot.stock_kg.to_string(),
+        }
+    }
+
+    fn to_lot(&self, id: u64) -> Result<Lot, String> {
+        fn number(value: &str, name: &str) -> Result<f32, String> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{} must be a number", name))
+        }
+
+        if self.origin.trim().is_empty() {
+            return Err("Origin is required".to_string());
+        }
+
+        Ok(Lot {
+            id,
+            origin: self.origin.trim().to_string(),
+            farm: self.farm.trim().to_string(),
+            process: self.process.trim().to_string(),
+            moisture: number(&self.moisture, "Moisture")?,
+            density: number(&self.density, "Density")?,
+            supplier: self.supplier.trim().to_string(),
+            purchase_date: NaiveDate::parse_from_str(self.purchase_date.trim(), "%Y-%m-%d")
+                .map_err(|_| "Purchase date must be YYYY-MM-DD".to_string())?,
+            stock_kg: number(&self.stock_kg, "Stock")?,
+        })
+    }
+}
+
+impl Inventory {
+    fn new(stock: Stock) -> Self {
+        Inventory {
+            lots: stock.lots,
+            next_id: stock.next_id,
+            ..Default::default()
+        }
+    }
+
+    fn data_file() -> PathBuf {
+        let mut path = PROJECT_DIRS.data_dir().join("_").to_path_buf();
+        path.set_file_name("inventory.json");
+        path
+    }
+
+    fn read() -> Result<Stock, Box<dyn Error>> {
+        match fs::read_to_string(Self::data_file()) {
+            Ok(string) => Ok(Stock::parse(&string)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Stock::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Loads the lots, starting empty only when there is no inventory yet.
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(stock) => Inventory::new(stock),
+            Err(error) => Inventory {
+                load_error: Some(format!(
+                    "{} could not be read, changes won't be saved: {}",
+                    Self::data_file().display(),
+                    error
+                )),
+                ..Inventory::new(Stock::default())
+            },
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if self.load_error.is_some() {
+            return Err("The inventory file is left untouched as it could not be read".into());
+        }
+
+        let path = Self::data_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let stock = Stock {
+            next_id: self.next_id,
+            lots: self.lots.clone(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&stock)?)?;
+        Ok(())
+    }
+
+    pub fn lots(&self) -> &[Lot] {
+        &self.lots
+    }
+
+    /// Removes the green weight of a roast from the stock of its lot.
+    pub fn deduct(&mut self, id: u64, kg: f32) {
+        if let Some(lot) = self.lots.iter_mut().find(|lot| lot.id == id) {
+            let short = (kg > lot.stock_kg).then(|| {
+                format!(
+                    "Lot #{} only had {:.2} kg left for a {:.2} kg batch, its stock is now 0",
+                    id, lot.stock_kg, kg
+                )
+            });
+            lot.stock_kg = (lot.stock_kg - kg).max(0.0);
+            self.error = self.save().err().map(|error| error.to_string()).or(short);
+        }
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::FieldChanged(field, value) => {
+                *self.form.field_mut(field) = value;
+            }
+            Message::Save => {
+                let id = self.editing.unwrap_or(self.next_id);
+
+                match self.form.to_lot(id) {
+                    Ok(lot) => {
+                        match self.lots.iter_mut().find(|l| l.id == id) {
+                            Some(existing) => *existing = lot,
+                            None => {
+                                self.lots.push(lot);
+                                self.next_id = id + 1;
+                            }
+                        }
+                        self.form = Form::default();
+                        self.editing = None;
+                        self.error = self.save().err().map(|error| error.to_string());
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+            }
+            Message::Edit(id) => {
+                if let Some(lot) = self.lots.iter().find(|lot| lot.id == id) {
+                    self.form = Form::from_lot(lot);
+                    self.editing = Some(id);
+                    self.error = None;
+                }
+            }
+            Message::Cancel => {
+                self.form = Form::default();
+                self.editing = None;
+                self.error = None;
+            }
+            Message::Delete(id) => {
+                self.lots.retain(|lot| lot.id != id);
+                if self.editing == Some(id) {
+                    self.form = Form::default();
+                    self.editing = None;
+                }
+                self.error = self.save().err().map(|error| error.to_string());
+            }
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = column![text("Green coffee").size(30)].push_maybe(
+            self.load_error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        );
+
+        let lots = scrollable(
+            column(self.lots.iter().map(|lot| {
+                row![
+                    column![
+                        text(format!("#{} {} – {}", lot.id, lot.origin, lot.farm)),
+                        text(format!(
+                            "{}, {:.1} % moisture, {:.0} g/L, {} ({})",
+                            lot.process,
+                            lot.moisture,
+                            lot.density,
+                            lot.supplier,
+                            lot.purchase_date.format("%Y-%m-%d")
+                        ))
+                        .size(14)
+                        .style(text::secondary),
+                    ],
+                    horizontal_space(),
+                    text(format!("{:.2} kg", lot.stock_kg)).size(20),
+                    button("Edit")
+                        .on_press(Message::Edit(lot.id))
+                        .style(button::secondary),
+                    button("Delete")
+                        .on_press(Message::Delete(lot.id))
+                        .style(button::danger),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(10),
+        )
+        .height(Fill);
+
+        let input = |label: &'static str, placeholder: &'static str, field: Field| {
+            let value = match field {
+                Field::Origin => &self.form.origin,
+                Field::Farm => &self.form.farm,
+                Field::Process => &self.form.process,
+                Field::Moisture => &self.form.moisture,
+                Field::Density => &self.form.density,
+                Field::Supplier => &self.form.supplier,
+                Field::PurchaseDate => &self.form.purchase_date,
+                Field::Stock => &self.form.stock_kg,
+            };
+
+            row![
+                text(label).width(150),
+                text_input(placeholder, value)
+                    .on_input(move |value| Message::FieldChanged(field, value))
+            ]
+            .align_y(Alignment::Center)
+        };
+
+        let mut actions = row![
+            button(if self.editing.is_some() {
+                "Save lot"
+            } else {
+                "Add lot"
+            })
+            .on_press(Message::Save)
+            .style(button::success)
+        ]
+        .spacing(10);
+        if self.editing.is_some() {
+            actions = actions.push(
+                button("Cancel")
+                    .on_press(Message::Cancel)
+                    .style(button::secondary),
+            );
+        }
+
+        let form = column![
+            input("Origin", "Nicaragua", Field::Origin),
+            input("Farm", "Finca ...", Field::Farm),
+            input("Process", "Washed", Field::Process),
+            input("Moisture (%)", "10.5", Field::Moisture),
+            input("Density (g/L)", "720", Field::Density),
+            input("Supplier", "Supplier", Field::Supplier),
+            input("Purchase date", "YYYY-MM-DD", Field::PurchaseDate),
+            input("Stock (kg)", "60", Field::Stock),
+            actions,
+        ]
+        .push_maybe(
+            self.error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10);
+
+        container(column![title, lots, form].max_width(800).spacing(20))
+            .center_x(Fill)
+            .padding(20)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(id: u64) -> Lot {
+        Lot {
+            id,
+            origin: "Ethiopia".to_string(),
+            farm: "Guji".to_string(),
+            process: "Washed".to_string(),
+            moisture: 10.5,
+            density: 720.0,
+            supplier: String::new(),
+            purchase_date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            stock_kg: 60.0,
+        }
+    }
+
+    #[test]
+    fn ids_of_deleted_lots_are_not_reused() {
+        let stock = Stock {
+            next_id: 4,
+            lots: vec![lot(1), lot(2)],
+        };
+        let stock = Stock::parse(&serde_json::to_string(&stock).unwrap()).unwrap();
+
+        assert_eq!(stock.next_id, 4);
+        assert_eq!(stock.lots.len(), 2);
+    }
+
+    #[test]
+    fn lots_without_counter() {
+        assert_eq!(Stock::parse("[]").unwrap().next_id, 1);
+
+        let lots = serde_json::to_string(&vec![lot(1), lot(3)]).unwrap();
+        let stock = Stock::parse(&lots).unwrap();
+        assert_eq!(stock.next_id, 4);
+        assert_eq!(stock.lots.len(), 2);
+    }
+}
@@ -0,0 +1,355 @@
+//! Roasts in progress are appended sample by sample to a journal, one JSON
+//! record per line, so that a crash loses at most the last reading. The
+//! journal is removed once the roast is saved, one left behind is recovered
+//! on the next launch.
+
+use iced::Color;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    archive::{self, EventKind},
+    batch,
+    filter::{self, Filter},
+    roast::{CurveSettings, Roast, RoastCurve},
+    sensor::TempData,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Curve {
+    id: usize,
+    name: String,
+    color: [f32; 3],
+    filter: filter::Settings,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    Start {
+        batch: batch::Start,
+        curves: Vec<Curve>,
+        /// Recording before charge.
+        #[serde(default)]
+        preheat: bool,
+    },
+    /// A raw reading of the curve at this index, times in seconds since the
+    /// recording started.
+    Sample {
+        curve: usize,
+        temp: f64,
+        time: f32,
+    },
+    Event {
+        kind: EventKind,
+        time: f32,
+    },
+    Charge {
+        time: f32,
+    },
+    /// Cleared when the roast is resumed.
+    Drop {
+        time: Option<f32>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    /// When the recording started, the charge may come later.
+    origin: Instant,
+}
+
+/// One journal per roaster, as each has at most one roast in progress.
+fn path(machine: &str) -> PathBuf {
+    let name: String = machine
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    archive::dir().join(format!("journal_{}.jsonl", name))
+}
+
+impl Journal {
+    /// Starts the journal of a roast, replacing any earlier one.
+    pub fn create(machine: &str, roast: &Roast) -> io::Result<Journal> {
+        let path = path(machine);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut journal = Journal {
+            file: File::create(&path)?,
+            path,
+            origin: roast.start_time,
+        };
+        journal.append(&Record::Start {
+            batch: roast.batch.clone(),
+            curves: roast
+                .curves
+                .iter()
+                .map(|curve| Curve {
+                    id: curve.source_id,
+                    name: curve.name.clone(),
+                    color: [curve.color.r, curve.color.g, curve.color.b],
+                    filter: curve.filter.settings().clone(),
+                })
+                .collect(),
+            preheat: !roast.charged,
+        })?;
+
+        Ok(journal)
+    }
+
+    fn append(&mut self, record: &Record) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // Unbuffered, a record is on disk as soon as it's written.
+        self.file.write_all(&line)
+    }
+
+    fn time(&self, at: Instant) -> f32 {
+        at.duration_since(self.origin).as_secs_f32()
+    }
+
+    pub fn sample(&mut self, curve: usize, temp_data: &TempData) -> io::Result<()> {
+        self.append(&Record::Sample {
+            curve,
+            temp: temp_data.temp,
+            time: self.time(temp_data.time),
+        })
+    }
+
+    pub fn event(&mut self, kind: EventKind, at: Instant) -> io::Result<()> {
+        self.append(&Record::Event {
+            kind,
+            time: self.time(at),
+        })
+    }
+
+    pub fn charge(&mut self, at: Instant) -> io::Result<()> {
+        self.append(&Record::Charge {
+            time: self.time(at),
+        })
+    }
+
+    pub fn drop_time(&mut self, at: Option<Instant>) -> io::Result<()> {
+        self.append(&Record::Drop {
+            time: at.map(|at| self.time(at)),
+        })
+    }
+
+    /// Removes the journal once its roast is saved or discarded.
+    pub fn remove(self) -> io::Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path).map_err(|error| {
+            io::Error::new(error.kind(), format!("{}: {}", self.path.display(), error))
+        })
+    }
+
+    /// Rebuilds the roast left in the journal of a roaster, if any. A
+    /// truncated last record is ignored, a pre-heat that never saw the
+    /// charge is dropped.
+    pub fn recover(machine: &str) -> Result<Option<(Roast, Journal)>, String> {
+        Self::recover_from(path(machine))
+    }
+
+    fn recover_from(path: PathBuf) -> Result<Option<(Roast, Journal)>, String> {
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.to_string()),
+        };
+
+        let mut records = bytes
+            .split(|byte| *byte == b'\n')
+            .filter_map(|line| serde_json::from_slice::<Record>(line).ok());
+
+        let Some(Record::Start {
+            batch,
+            curves,
+            preheat,
+        }) = records.next()
+        else {
+            return Err(format!("{} doesn't start with a roast", path.display()));
+        };
+
+        let curves = curves
+            .into_iter()
+            .map(|curve| {
+                let [r, g, b] = curve.color;
+                RoastCurve::new(
+                    curve.id,
+                    &curve.name,
+                    Color::from_rgb(r, g, b),
+                    CurveSettings::temperature(),
+                    Filter::new(curve.filter),
+                )
+            })
+            .collect();
+        let mut roast = Roast {
+            charged: !preheat,
+            ..Roast::new(curves, CurveSettings::time(), batch)
+        };
+        let origin = roast.start_time;
+        let at = move |time: f32| origin + Duration::from_secs_f32(time.max(0.0));
+
+        for record in records {
+            match record {
+                Record::Sample { curve, temp, time } => {
+                    let temp_data = TempData {
+                        temp,
+                        time: at(time),
+                    };
+                    if let Some(curve) = roast.curves.get_mut(curve) {
+                        curve.push(&temp_data);
+                    }
+                    roast.last_time = roast.last_time.max(temp_data.time);
+                }
+                Record::Event { kind, time } => {
+                    roast.events.retain(|(k, _)| *k != kind);
+                    roast.events.push((kind, at(time)));
+                }
+                Record::Charge { time } => roast.charge(at(time)),
+                Record::Drop { time } => roast.drop_time = time.map(at),
+                Record::Start { .. } => {}
+            }
+        }
+
+        if !roast.charged {
+            fs::remove_file(&path).map_err(|error| error.to_string())?;
+            return Ok(None);
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map_err(|error| error.to_string())?;
+        // The next record must not be glued to a truncated one.
+        if !bytes.ends_with(b"\n") {
+            file.write_all(b"\n").map_err(|error| error.to_string())?;
+        }
+
+        Ok(Some((roast, Journal { path, file, origin })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(name: &str, records: &[Record], tail: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("journal-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.jsonl"));
+
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(record).unwrap());
+            lines.push('\n');
+        }
+        lines.push_str(tail);
+        fs::write(&path, lines).unwrap();
+        path
+    }
+
+    fn start(preheat: bool) -> Record {
+        Record::Start {
+            batch: batch::Start::default(),
+            curves: vec![Curve {
+                id: 0,
+                name: "Bean".to_string(),
+                color: [1.0, 0.0, 0.0],
+                filter: filter::Settings {
+                    median: 1,
+                    smoothing: 1.0,
+                    ..filter::Settings::default()
+                },
+            }],
+            preheat,
+        }
+    }
+
+    fn sample(temp: f64, time: f32) -> Record {
+        Record::Sample {
+            curve: 0,
+            temp,
+            time,
+        }
+    }
+
+    #[test]
+    fn truncated_last_record_is_ignored() {
+        let path = journal(
+            "truncated",
+            &[start(false), sample(200.0, 0.0), sample(150.0, 1.0)],
+            r#"{"sample":{"curve":0,"te"#,
+        );
+
+        let (roast, mut journal) = Journal::recover_from(path.clone()).unwrap().unwrap();
+        assert_eq!(roast.curves[0].raw.len(), 2);
+        assert_eq!(roast.curves[0].raw[1].temp, 150.0);
+
+        journal.append(&sample(140.0, 2.0)).unwrap();
+        let (roast, journal) = Journal::recover_from(path).unwrap().unwrap();
+        journal.remove().unwrap();
+        assert_eq!(roast.curves[0].raw.len(), 3);
+        assert_eq!(roast.curves[0].raw[2].temp, 140.0);
+    }
+
+    #[test]
+    fn preheat_without_charge_is_dropped() {
+        let path = journal(
+            "preheat",
+            &[start(true), sample(180.0, 0.0), sample(185.0, 1.0)],
+            "",
+        );
+
+        assert!(Journal::recover_from(path.clone()).unwrap().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn preheat_with_charge_is_recovered() {
+        let path = journal(
+            "charged",
+            &[
+                start(true),
+                sample(180.0, 0.0),
+                Record::Charge { time: 5.0 },
+                sample(170.0, 6.0),
+            ],
+            "",
+        );
+
+        let (roast, journal) = Journal::recover_from(path).unwrap().unwrap();
+        assert!(roast.charged);
+        assert_eq!(roast.start_time - journal.origin, Duration::from_secs(5));
+        journal.remove().unwrap();
+    }
+
+    #[test]
+    fn drop_cleared_after_resume() {
+        let path = journal(
+            "resumed",
+            &[
+                start(false),
+                sample(200.0, 0.0),
+                Record::Drop { time: Some(60.0) },
+                Record::Drop { time: None },
+                sample(210.0, 70.0),
+            ],
+            "",
+        );
+
+        let (roast, journal) = Journal::recover_from(path).unwrap().unwrap();
+        journal.remove().unwrap();
+        assert_eq!(roast.drop_time, None);
+        assert_eq!(roast.curves[0].raw.len(), 2);
+    }
+}
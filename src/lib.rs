@@ -1,36 +1,81 @@
 use iced::{
     Element, Event, Subscription, Task, Theme,
     keyboard::{self, key},
+    task,
     widget::{self, row},
 };
+use tokio::sync::mpsc;
 
+mod archive;
+mod batch;
+pub mod cli;
+mod comparison;
+mod convert;
+mod cupping;
 mod data;
+mod detect;
+mod discovery;
+mod filter;
+mod format;
+mod health;
 mod icons;
+mod inventory;
+mod journal;
+mod logger;
+mod manual;
+mod modbus;
+mod mqtt;
 mod preferences;
+mod production;
 mod recipe;
+mod reconnect;
+mod report;
+mod roast;
+mod roasters;
 mod roasting;
 mod sensor;
+mod serial;
+mod server;
 mod settings;
 mod sidebar;
+mod stats;
 
+use comparison::Comparison;
+use cupping::Cuppings;
+use inventory::Inventory;
 use preferences::Preferences;
+use production::Production;
 use recipe::Recipe;
-use roasting::Roasting;
+use roasters::Roasters;
 use settings::Settings;
 use sidebar::{Sidebar, Tab};
+use stats::Statistics;
 
 pub struct App {
     screen: Screen,
     sidebar: Sidebar,
     recipe: Recipe,
-    roasting: Roasting,
+    roasters: Roasters,
+    inventory: Inventory,
+    production: Production,
+    cupping: Cuppings,
+    comparison: Comparison,
+    statistics: Statistics,
     settings: Settings,
+    hub: server::Hub,
+    server: Option<task::Handle>,
+    mqtt: Option<(mpsc::UnboundedSender<mqtt::Publication>, task::Handle)>,
 }
 
 #[derive(Clone, Debug)]
 pub enum Screen {
     Recipe,
     Roasting,
+    Inventory,
+    Production,
+    Cupping,
+    Comparison,
+    Statistics,
     Settings,
 }
 
@@ -47,6 +92,11 @@ impl TryFrom<usize> for Screen {
         match v {
             x if x == Screen::Recipe as usize => Ok(Screen::Recipe),
             x if x == Screen::Roasting as usize => Ok(Screen::Roasting),
+            x if x == Screen::Inventory as usize => Ok(Screen::Inventory),
+            x if x == Screen::Production as usize => Ok(Screen::Production),
+            x if x == Screen::Cupping as usize => Ok(Screen::Cupping),
+            x if x == Screen::Comparison as usize => Ok(Screen::Comparison),
+            x if x == Screen::Statistics as usize => Ok(Screen::Statistics),
             x if x == Screen::Settings as usize => Ok(Screen::Settings),
             _ => Err(()),
         }
@@ -58,36 +108,109 @@ pub enum Message {
     ScreenSelected(Screen),
     Sidebar(sidebar::Message),
     Recipe(recipe::Message),
-    Roasting(roasting::Message),
+    Roasting(roasters::Message),
+    Inventory(inventory::Message),
+    Production(production::Message),
+    Cupping(cupping::Message),
+    Comparison(comparison::Message),
+    Statistics(stats::Message),
     Settings(settings::Message),
+    ServerStopped(Result<(), String>),
+    /// The broker connection was lost, or is back with `None`.
+    MqttStatus(Option<String>),
+    MqttStopped(Result<(), String>),
     Event(Event),
+    /// F11 switches the roasting screen to the large display and back.
+    ToggleDisplay,
 }
 
 impl App {
     pub fn boot() -> (App, Task<Message>) {
-        let preferences = Preferences::load();
+        let preferences = Preferences::load().unwrap();
 
-        let (roasting, task) = Roasting::boot();
+        let (roasters, task) = Roasters::boot(&preferences.machines, &preferences.sensors);
+
+        let mut app = App {
+            settings: Settings::new(preferences),
+            screen: Screen::default(),
+            sidebar: Sidebar::new(
+                vec![
+                    Tab::icon('\u{E801}'),
+                    Tab::icon('\u{F275}'),
+                    Tab::text("Lots"),
+                    Tab::text("Queue"),
+                    Tab::text("Cupping"),
+                    Tab::text("Compare"),
+                    Tab::text("Stats"),
+                    Tab::icon('\u{E800}'),
+                ],
+                0,
+            ),
+            recipe: Recipe::new(),
+            roasters,
+            inventory: Inventory::load(),
+            production: Production::load(),
+            cupping: Cuppings::load(),
+            comparison: Comparison::new(),
+            statistics: Statistics::new(),
+            hub: server::Hub::default(),
+            server: None,
+            mqtt: None,
+        };
+        let server = app.restart_server();
+        let mqtt = app.restart_mqtt();
+        let archive = Task::batch([
+            app.cupping.refresh().map(Message::Cupping),
+            app.comparison.refresh().map(Message::Comparison),
+            app.statistics.refresh().map(Message::Statistics),
+        ]);
 
         (
-            App {
-                settings: Settings::new(preferences.unwrap()),
-                screen: Screen::default(),
-                sidebar: Sidebar::new(
-                    vec![
-                        Tab::icon('\u{E801}'),
-                        Tab::icon('\u{F275}'),
-                        Tab::icon('\u{E800}'),
-                    ],
-                    0,
-                ),
-                recipe: Recipe::new(),
-                roasting,
-            },
-            task.map(Message::Roasting),
+            app,
+            Task::batch([task.map(Message::Roasting), server, mqtt, archive]),
         )
     }
 
+    /// Stops the API server, if any, and starts it again with the current
+    /// settings when enabled.
+    fn restart_server(&mut self) -> Task<Message> {
+        self.server = None;
+
+        let config = self.settings.server().clone();
+        if !config.enabled {
+            return Task::none();
+        }
+
+        let (task, handle) = Task::perform(
+            server::serve(config, self.hub.clone()),
+            Message::ServerStopped,
+        )
+        .abortable();
+        self.server = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Stops the MQTT publisher, if any, and starts it again with the
+    /// current settings when enabled.
+    fn restart_mqtt(&mut self) -> Task<Message> {
+        self.mqtt = None;
+
+        let config = self.settings.mqtt().clone();
+        if !config.enabled {
+            return Task::none();
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (task, handle) = Task::sip(
+            mqtt::publish(config, receiver),
+            Message::MqttStatus,
+            Message::MqttStopped,
+        )
+        .abortable();
+        self.mqtt = Some((sender, handle.abort_on_drop()));
+        task
+    }
+
     pub fn update(app: &mut App, message: Message) -> Task<Message> {
         match message {
             Message::ScreenSelected(selected) => {
@@ -110,9 +233,103 @@ impl App {
                 app.recipe.update(message);
                 Task::none()
             }
-            Message::Roasting(message) => app.roasting.update(message).map(Message::Roasting),
-            Message::Settings(message) => {
-                app.settings.update(message);
+            Message::Roasting(message) => {
+                let mut refresh = Task::none();
+                if let roasters::Message::Roaster(_, roasting::Message::Saved(batch)) = &message {
+                    if let Some(lot) = batch.lot {
+                        app.inventory.deduct(lot, batch.green_weight);
+                    }
+                    if let Some(next) = batch
+                        .planned
+                        .and_then(|id| app.production.complete(id, batch.green_weight))
+                    {
+                        app.roasters.preload(&next);
+                    }
+                    refresh = Task::batch([
+                        app.cupping.refresh().map(Message::Cupping),
+                        app.comparison.refresh().map(Message::Comparison),
+                        app.statistics.refresh().map(Message::Statistics),
+                    ]);
+                }
+                let tick = matches!(
+                    message,
+                    roasters::Message::Roaster(_, roasting::Message::Tick(_))
+                );
+                let published = app.mqtt.is_some().then(|| message.clone());
+                let task = app.roasters.update(message).map(Message::Roasting);
+
+                if let (Some((sender, _)), Some(message)) = (&app.mqtt, published) {
+                    if let Some(publication) = app.roasters.publication(&message) {
+                        let _ = sender.send(publication);
+                    }
+                }
+
+                if app.server.is_some() {
+                    app.hub.publish(app.roasters.live());
+                    if tick {
+                        app.hub.publish_roast(app.roasters.roast_data());
+                    }
+                }
+                Task::batch([task, refresh])
+            }
+            Message::Inventory(message) => {
+                app.inventory.update(message);
+                Task::none()
+            }
+            Message::Cupping(message) => app.cupping.update(message).map(Message::Cupping),
+            Message::Comparison(message) => app.comparison.update(message).map(Message::Comparison),
+            Message::Statistics(message) => app.statistics.update(message).map(Message::Statistics),
+            Message::Production(message) => {
+                if let Some(batch) = app.production.update(message) {
+                    app.roasters.preload(&batch);
+                    app.screen = Screen::Roasting;
+                    app.sidebar
+                        .update(sidebar::Message::TabSelected(Screen::Roasting as usize));
+                }
+                Task::none()
+            }
+            Message::Settings(message) => match app.settings.update(message) {
+                settings::Action::None => Task::none(),
+                settings::Action::Run(task) => task.map(Message::Settings),
+                settings::Action::SensorAdded(config) => {
+                    app.roasters.add_sensor(&config).map(Message::Roasting)
+                }
+                settings::Action::SensorRemoved(index) => {
+                    app.roasters.remove_sensor(index);
+                    Task::none()
+                }
+                settings::Action::SensorMoved(index, config) => app
+                    .roasters
+                    .move_sensor(index, &config)
+                    .map(Message::Roasting),
+                settings::Action::FilterChanged(index, filter) => {
+                    app.roasters.set_filter(index, filter);
+                    Task::none()
+                }
+                settings::Action::MachineAdded(name) => {
+                    app.roasters.add_machine(&name);
+                    Task::none()
+                }
+                settings::Action::RemoveMachine(name) => {
+                    let removed = app.roasters.remove_machine(&name);
+                    app.settings.remove_machine(&name, removed);
+                    Task::none()
+                }
+                settings::Action::ServerChanged(_) => app.restart_server(),
+                settings::Action::MqttChanged(_) => app.restart_mqtt(),
+            },
+            Message::ServerStopped(result) => {
+                app.server = None;
+                app.settings.set_server_error(result.err());
+                Task::none()
+            }
+            Message::MqttStatus(error) => {
+                app.settings.set_mqtt_error(error);
+                Task::none()
+            }
+            Message::MqttStopped(result) => {
+                app.mqtt = None;
+                app.settings.set_mqtt_error(result.err());
                 Task::none()
             }
             Message::Event(event) => match event {
@@ -129,11 +346,22 @@ impl App {
                 }
                 _ => Task::none(),
             },
+            Message::ToggleDisplay if matches!(app.screen, Screen::Roasting) => app
+                .roasters
+                .update(roasters::Message::ToggleDisplay)
+                .map(Message::Roasting),
+            Message::ToggleDisplay => Task::none(),
         }
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        self.roasting.subscription().map(Message::Roasting)
+        Subscription::batch([
+            self.roasters.subscription().map(Message::Roasting),
+            keyboard::on_key_press(|key, _| {
+                matches!(key, keyboard::Key::Named(key::Named::F11))
+                    .then_some(Message::ToggleDisplay)
+            }),
+        ])
     }
 
     pub fn view(app: &App) -> Element<Message> {
@@ -141,10 +369,26 @@ impl App {
 
         let screen = match &app.screen {
             Screen::Recipe => app.recipe.view().map(Message::Recipe),
-            Screen::Roasting => app.roasting.view().map(Message::Roasting),
+            Screen::Roasting => app
+                .roasters
+                .view(app.inventory.lots())
+                .map(Message::Roasting),
+            Screen::Inventory => app.inventory.view().map(Message::Inventory),
+            Screen::Production => app
+                .production
+                .view(app.inventory.lots())
+                .map(Message::Production),
+            Screen::Cupping => app.cupping.view(app.inventory.lots()).map(Message::Cupping),
+            Screen::Comparison => app.comparison.view().map(Message::Comparison),
+            Screen::Statistics => app.statistics.view().map(Message::Statistics),
             Screen::Settings => app.settings.view().map(Message::Settings),
         };
 
+        // The operator display takes the whole window.
+        if matches!(app.screen, Screen::Roasting) && app.roasters.is_display() {
+            return screen;
+        }
+
         row![sidebar, screen].into()
     }
 
@@ -0,0 +1,331 @@
+use chrono::Local;
+use iced::{
+    Color,
+    futures::{
+        StreamExt,
+        channel::{mpsc, oneshot},
+    },
+    task::Sipper,
+};
+use std::{error::Error, time::Instant};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    archive::{self, EventKind, RawRoastData},
+    batch,
+    filter::Filter,
+    preferences::Preferences,
+    reconnect::Backoff,
+    roast::{CurveSettings, Roast, RoastCurve},
+    sensor::{self, TempData},
+};
+
+/// Batch defaults for roasts recorded without the window.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Local TCP port accepting the same commands as the keyboard.
+    pub port: Option<u16>,
+    pub recipe: Option<String>,
+    pub green_weight: f32,
+    pub operator: String,
+    pub machine: String,
+}
+
+#[derive(Debug)]
+enum Command {
+    /// Start when idle, stop when recording.
+    Toggle,
+    Start(Option<f32>),
+    Stop,
+    Mark(EventKind),
+    Status,
+    Quit,
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None, _) => Ok(Command::Toggle),
+            (Some("start"), weight) => match weight.map(str::parse).transpose() {
+                Ok(weight) => Ok(Command::Start(weight)),
+                Err(_) => Err("the green weight must be a number".to_string()),
+            },
+            (Some("stop"), None) => Ok(Command::Stop),
+            (Some("mark"), Some(key)) => EventKind::from_key(key)
+                .map(Command::Mark)
+                .ok_or_else(|| format!("unknown event {}", key)),
+            (Some("status"), None) => Ok(Command::Status),
+            (Some("quit"), None) => Ok(Command::Quit),
+            _ => Err(
+                "commands: start [kg], stop, mark dry_end|first_crack|second_crack, status, quit"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+enum Input {
+    Reading(usize, TempData),
+    Lost(usize, String),
+    Command(Command, Option<oneshot::Sender<String>>),
+}
+
+/// Keeps a sensor connected, reconnecting with the same backoff as the
+/// window does.
+async fn watch(index: usize, config: sensor::Config, input: mpsc::UnboundedSender<Input>) {
+    let mut backoff = Backoff::default();
+
+    loop {
+        backoff.start_attempt();
+        let mut connection = sensor::connect(&config).pin();
+
+        while let Some(event) = connection.sip().await {
+            match event {
+                sensor::Event::Change(temp_data) => {
+                    let _ = input.unbounded_send(Input::Reading(index, temp_data));
+                }
+                sensor::Event::Attach => {
+                    backoff.reset();
+                    backoff.log(&config.name, "Connected".to_string());
+                }
+                sensor::Event::Detach => {}
+            }
+        }
+
+        let message = match connection.await {
+            Ok(()) => "Disconnected".to_string(),
+            Err(error) => format!("Error: {}", error),
+        };
+        backoff.log(&config.name, message.clone());
+        let _ = input.unbounded_send(Input::Lost(index, message));
+
+        backoff.schedule(Instant::now());
+        if let Some(next_at) = backoff.next_at() {
+            tokio::time::sleep_until(next_at.into()).await;
+        }
+    }
+}
+
+async fn keyboard(input: mpsc::UnboundedSender<Input>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        match Command::parse(&line) {
+            Ok(command) => {
+                let _ = input.unbounded_send(Input::Command(command, None));
+            }
+            Err(error) => println!("{}", error),
+        }
+    }
+}
+
+async fn client(stream: TcpStream, input: mpsc::UnboundedSender<Input>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match Command::parse(&line) {
+            Ok(command) => {
+                let (reply, response) = oneshot::channel();
+                let _ = input.unbounded_send(Input::Command(command, Some(reply)));
+                response.await.unwrap_or_default()
+            }
+            Err(error) => error,
+        };
+        if writer
+            .write_all(format!("{}\n", response).as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+async fn listen(listener: TcpListener, input: mpsc::UnboundedSender<Input>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(client(stream, input.clone()));
+    }
+}
+
+struct Logger {
+    options: Options,
+    sensors: Vec<sensor::Config>,
+    latest: Vec<Option<TempData>>,
+    roast: Option<Roast>,
+}
+
+impl Logger {
+    fn record(&mut self, index: usize, temp_data: TempData) {
+        if let Some(roast) = &mut self.roast {
+            if let Some(curve) = roast.curves.iter_mut().find(|c| c.source_id == index) {
+                curve.push(&temp_data);
+            }
+            roast.last_time = temp_data.time;
+        }
+        self.latest[index] = Some(temp_data);
+    }
+
+    fn start(&mut self, green_weight: Option<f32>) -> String {
+        if self.roast.is_some() {
+            return "Already recording".to_string();
+        }
+
+        let curves = self
+            .sensors
+            .iter()
+            .enumerate()
+            .map(|(index, config)| {
+                let [r, g, b] = config.color;
+                RoastCurve::new(
+                    index,
+                    &config.name,
+                    Color::from_rgb(r, g, b),
+                    CurveSettings::temperature(),
+                    Filter::new(config.filter.clone()),
+                )
+            })
+            .collect();
+        let batch = batch::Start {
+            lot: None,
+            recipe: self.options.recipe.clone(),
+            green_weight: green_weight.unwrap_or(self.options.green_weight),
+            ambient_temp: None,
+            humidity: None,
+            operator: self.options.operator.clone(),
+            machine: self.options.machine.clone(),
+            order: None,
+            planned: None,
+            started_at: Local::now(),
+        };
+        self.roast = Some(Roast::new(curves, CurveSettings::time(), batch));
+
+        "Recording".to_string()
+    }
+
+    fn stop(&mut self) -> String {
+        let Some(roast) = self.roast.take() else {
+            return "Not recording".to_string();
+        };
+
+        let raw_roast_data: RawRoastData = (&roast).into();
+        match archive::save(&raw_roast_data) {
+            Ok(path) => format!("Saved to {}", path.display()),
+            Err(error) => format!("Could not save the roast: {}", error),
+        }
+    }
+
+    fn status(&self) -> String {
+        let state = match &self.roast {
+            Some(roast) => format!("Recording for {} s", roast.elapsed().as_secs()),
+            None => "Idle".to_string(),
+        };
+        let temps: Vec<String> = self
+            .sensors
+            .iter()
+            .zip(&self.latest)
+            .map(|(config, latest)| match latest {
+                Some(temp_data) => format!("{} {:.1} °C", config.name, temp_data.temp),
+                None => format!("{} –", config.name),
+            })
+            .collect();
+
+        format!("{}, {}", state, temps.join(", "))
+    }
+
+    /// Runs a command, returning the response and whether to quit.
+    fn execute(&mut self, command: Command) -> (String, bool) {
+        match command {
+            Command::Toggle if self.roast.is_some() => (self.stop(), false),
+            Command::Toggle => (self.start(None), false),
+            Command::Start(green_weight) => (self.start(green_weight), false),
+            Command::Stop => (self.stop(), false),
+            Command::Mark(kind) => match &mut self.roast {
+                Some(roast) => {
+                    roast.mark(kind);
+                    (format!("{} marked", kind), false)
+                }
+                None => ("Not recording".to_string(), false),
+            },
+            Command::Status => (self.status(), false),
+            Command::Quit if self.roast.is_some() => (format!("{}, bye", self.stop()), true),
+            Command::Quit => ("Bye".to_string(), true),
+        }
+    }
+}
+
+async fn log(options: Options) -> Result<(), Box<dyn Error>> {
+    let preferences = Preferences::load()?;
+    // Only the sensors of the roaster when it is one of the configured ones.
+    let sensors = match preferences
+        .machines
+        .iter()
+        .position(|m| *m == options.machine)
+    {
+        Some(index) => preferences
+            .sensors
+            .into_iter()
+            .filter(|sensor| {
+                sensor.machine == options.machine
+                    || (index == 0 && !preferences.machines.contains(&sensor.machine))
+            })
+            .collect(),
+        None => preferences.sensors,
+    };
+    let (input, mut inputs) = mpsc::unbounded();
+
+    for (index, config) in sensors.iter().enumerate() {
+        tokio::spawn(watch(index, config.clone(), input.clone()));
+    }
+    tokio::spawn(keyboard(input.clone()));
+    if let Some(port) = options.port {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        tokio::spawn(listen(listener, input.clone()));
+        println!("Listening for commands on 127.0.0.1:{}", port);
+    }
+    println!("Press Enter to start or stop a roast, type `quit` to exit");
+
+    let mut logger = Logger {
+        options,
+        latest: vec![None; sensors.len()],
+        sensors,
+        roast: None,
+    };
+
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Reading(index, temp_data) => logger.record(index, temp_data),
+            Input::Lost(index, message) => {
+                println!("{}: {}", logger.sensors[index].name, message);
+            }
+            Input::Command(command, reply) => {
+                let (response, quit) = logger.execute(command);
+                println!("{}", response);
+                if let Some(reply) = reply {
+                    let _ = reply.send(response);
+                }
+                if quit {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Logs roasts from the configured sensors until `quit` is received.
+pub fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let result = runtime.block_on(log(options));
+
+    // Reading stdin blocks a thread that would otherwise delay the exit.
+    runtime.shutdown_background();
+    result
+}
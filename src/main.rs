@@ -1,7 +1,17 @@
-use cambio_torrefaction::App;
+use cambio_torrefaction::{App, cli};
 use iced::window;
+use std::process;
 
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let code = cli::run(&args).unwrap_or_else(|error| {
+            eprintln!("error: {}", error);
+            1
+        });
+        process::exit(code);
+    }
+
     iced::application(App::boot, App::update, App::view)
         .title("Cambio Torréfaction")
         .subscription(App::subscription)
@@ -0,0 +1,328 @@
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    widget::{button, checkbox, column, pick_list, row, text, text_input},
+};
+use std::fmt;
+
+use crate::{
+    discovery::COLORS,
+    filter,
+    modbus::{self, Register, Transport},
+    sensor,
+    serial::{self, Protocol},
+};
+
+/// Sensors that can't be discovered and are added by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    ModbusTcp,
+    ModbusRtu,
+    Serial,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] = [Kind::ModbusTcp, Kind::ModbusRtu, Kind::Serial];
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::ModbusTcp => write!(f, "Modbus TCP"),
+            Kind::ModbusRtu => write!(f, "Modbus RTU (serial)"),
+            Kind::Serial => write!(f, "Serial thermometer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Field {
+    Name,
+    Host,
+    Port,
+    Path,
+    BaudRate,
+    Unit,
+    Address,
+    Scale,
+    Offset,
+    Interval,
+    Prefix,
+    Channel,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    KindSelected(Kind),
+    RegisterSelected(Register),
+    ProtocolSelected(Protocol),
+    SignedToggled(bool),
+    FieldChanged(Field, String),
+    Add,
+}
+
+#[derive(Debug)]
+pub struct Form {
+    kind: Option<Kind>,
+    name: String,
+    host: String,
+    port: String,
+    path: String,
+    baud_rate: String,
+    unit: String,
+    register: Register,
+    address: String,
+    signed: bool,
+    scale: String,
+    offset: String,
+    interval: String,
+    protocol: Protocol,
+    prefix: String,
+    channel: String,
+    error: Option<String>,
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Form {
+            kind: None,
+            name: String::new(),
+            host: "localhost".to_string(),
+            port: "502".to_string(),
+            path: String::new(),
+            baud_rate: "9600".to_string(),
+            unit: "1".to_string(),
+            register: Register::Holding,
+            address: "0".to_string(),
+            signed: true,
+            scale: "0.1".to_string(),
+            offset: "0".to_string(),
+            interval: "1000".to_string(),
+            protocol: Protocol::Lines,
+            prefix: String::new(),
+            channel: "1".to_string(),
+            error: None,
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} must be a number", name))
+}
+
+impl Form {
+    fn source(&self, kind: Kind) -> Result<sensor::Source, String> {
+        if kind == Kind::Serial {
+            return Ok(sensor::Source::Serial(serial::Config {
+                path: self.path.trim().to_string(),
+                baud_rate: number(&self.baud_rate, "Baud rate")?,
+                protocol: self.protocol,
+                prefix: self.prefix.trim().to_string(),
+                channel: number(&self.channel, "Channel")?,
+            }));
+        }
+
+        let transport = match kind {
+            Kind::ModbusTcp => Transport::Tcp {
+                host: self.host.trim().to_string(),
+                port: number(&self.port, "Port")?,
+            },
+            Kind::ModbusRtu | Kind::Serial => Transport::Rtu {
+                path: self.path.trim().to_string(),
+                baud_rate: number(&self.baud_rate, "Baud rate")?,
+            },
+        };
+
+        Ok(sensor::Source::Modbus(modbus::Config {
+            transport,
+            unit: number(&self.unit, "Unit id")?,
+            register: self.register,
+            address: number(&self.address, "Register address")?,
+            signed: self.signed,
+            scale: number(&self.scale, "Scale")?,
+            offset: number(&self.offset, "Offset")?,
+            interval: number(&self.interval, "Polling interval")?,
+        }))
+    }
+
+    /// Handles a message, returning a sensor to add to the configuration
+    /// once the form is complete.
+    pub fn update(
+        &mut self,
+        message: Message,
+        configured: &[sensor::Config],
+    ) -> Option<sensor::Config> {
+        match message {
+            Message::KindSelected(kind) => self.kind = Some(kind),
+            Message::RegisterSelected(register) => self.register = register,
+            Message::ProtocolSelected(protocol) => self.protocol = protocol,
+            Message::SignedToggled(signed) => self.signed = signed,
+            Message::FieldChanged(field, value) => {
+                *match field {
+                    Field::Name => &mut self.name,
+                    Field::Host => &mut self.host,
+                    Field::Port => &mut self.port,
+                    Field::Path => &mut self.path,
+                    Field::BaudRate => &mut self.baud_rate,
+                    Field::Unit => &mut self.unit,
+                    Field::Address => &mut self.address,
+                    Field::Scale => &mut self.scale,
+                    Field::Offset => &mut self.offset,
+                    Field::Interval => &mut self.interval,
+                    Field::Prefix => &mut self.prefix,
+                    Field::Channel => &mut self.channel,
+                } = value;
+            }
+            Message::Add => {
+                let kind = self.kind?;
+                if self.name.trim().is_empty() {
+                    self.error = Some("The sensor needs a name".to_string());
+                    return None;
+                }
+
+                match self.source(kind) {
+                    Ok(source) => {
+                        let config = sensor::Config {
+                            name: self.name.trim().to_string(),
+                            color: COLORS[configured.len() % COLORS.len()],
+                            hub_port: 0,
+                            serial_number: 0,
+                            channel: 0,
+                            filter: filter::Settings::default(),
+                            source,
+                            machine: String::new(),
+                        };
+                        *self = Form {
+                            kind: self.kind,
+                            ..Form::default()
+                        };
+                        return Some(config);
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+            }
+        }
+        None
+    }
+
+    fn input<'a>(
+        &'a self,
+        label: &'a str,
+        placeholder: &'a str,
+        field: Field,
+    ) -> Element<'a, Message> {
+        let value = match field {
+            Field::Name => &self.name,
+            Field::Host => &self.host,
+            Field::Port => &self.port,
+            Field::Path => &self.path,
+            Field::BaudRate => &self.baud_rate,
+            Field::Unit => &self.unit,
+            Field::Address => &self.address,
+            Field::Scale => &self.scale,
+            Field::Offset => &self.offset,
+            Field::Interval => &self.interval,
+            Field::Prefix => &self.prefix,
+            Field::Channel => &self.channel,
+        };
+
+        row![
+            text(label).width(Fill),
+            text_input(placeholder, value)
+                .on_input(move |value| Message::FieldChanged(field, value))
+                .width(Fill),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let choose = row![
+            text("Other sensors:").width(Fill),
+            pick_list(Kind::ALL, self.kind, Message::KindSelected)
+                .placeholder("Add a sensor...")
+                .width(Fill),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let Some(kind) = self.kind else {
+            return choose.into();
+        };
+
+        let connection = match kind {
+            Kind::ModbusTcp => column![
+                self.input("Host", "192.168.1.10", Field::Host),
+                self.input("Port", "502", Field::Port),
+            ],
+            Kind::ModbusRtu | Kind::Serial => column![
+                self.input("Serial port", "/dev/ttyUSB0", Field::Path),
+                self.input("Baud rate", "9600", Field::BaudRate),
+            ],
+        }
+        .spacing(10);
+
+        let reading = match kind {
+            Kind::ModbusTcp | Kind::ModbusRtu => column![
+                self.input("Unit id", "1", Field::Unit),
+                row![
+                    pick_list(
+                        Register::ALL,
+                        Some(self.register),
+                        Message::RegisterSelected
+                    )
+                    .width(Fill),
+                    text_input("0", &self.address)
+                        .on_input(|value| Message::FieldChanged(Field::Address, value))
+                        .width(Fill),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+                checkbox("Signed value", self.signed).on_toggle(Message::SignedToggled),
+                self.input("Scale", "0.1", Field::Scale),
+                self.input("Offset (°C)", "0", Field::Offset),
+                self.input("Polling interval (ms)", "1000", Field::Interval),
+            ],
+            Kind::Serial => column![
+                row![
+                    text("Protocol").width(Fill),
+                    pick_list(
+                        Protocol::ALL,
+                        Some(self.protocol),
+                        Message::ProtocolSelected
+                    )
+                    .width(Fill),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            ]
+            .push_maybe(
+                (self.protocol == Protocol::Lines)
+                    .then(|| self.input("Line prefix", "C =", Field::Prefix)),
+            )
+            .push(self.input("Channel", "1", Field::Channel)),
+        }
+        .spacing(10);
+
+        column![
+            choose,
+            self.input("Name", "Bean", Field::Name),
+            connection,
+            reading,
+            button(text("Add sensor").size(14))
+                .on_press(Message::Add)
+                .style(button::success),
+        ]
+        .push_maybe(
+            self.error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10)
+        .into()
+    }
+}
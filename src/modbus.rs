@@ -0,0 +1,148 @@
+//! Temperatures read from the registers of a PLC over Modbus TCP or RTU.
+//!
+//! Try it against a simulator, e.g. `diagslave -m tcp -p 5020` with a sensor
+//! on `localhost:5020`, unit 1, holding register 0 and a scale of 0.1.
+
+use iced::task::{Straw, sipper};
+use serde::{Deserialize, Serialize};
+use std::{fmt, io, time::Duration};
+use tokio::time::{self, MissedTickBehavior};
+use tokio_modbus::{
+    Slave,
+    client::{Context, Reader, rtu, tcp},
+};
+use tokio_serial::SerialStream;
+
+use crate::sensor::{Error, Event, TempData};
+
+/// How long to wait for a register before giving up on the connection.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transport {
+    Tcp { host: String, port: u16 },
+    Rtu { path: String, baud_rate: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Register {
+    Holding,
+    Input,
+}
+
+impl Register {
+    pub const ALL: [Register; 2] = [Register::Holding, Register::Input];
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::Holding => write!(f, "Holding register"),
+            Register::Input => write!(f, "Input register"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub transport: Transport,
+    pub unit: u8,
+    pub register: Register,
+    pub address: u16,
+    /// Whether the register holds a two's complement value.
+    pub signed: bool,
+    /// The temperature is `value × scale + offset`, e.g. a scale of 0.1 for
+    /// registers in tenths of a degree.
+    pub scale: f64,
+    pub offset: f64,
+    /// Polling interval in milliseconds.
+    pub interval: u64,
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.transport {
+            Transport::Tcp { host, port } => write!(f, "Modbus TCP {}:{}", host, port)?,
+            Transport::Rtu { path, baud_rate } => {
+                write!(f, "Modbus RTU {} at {} baud", path, baud_rate)?
+            }
+        }
+        write!(
+            f,
+            ", unit {}, {} {}",
+            self.unit,
+            self.register.to_string().to_lowercase(),
+            self.address
+        )
+    }
+}
+
+impl Config {
+    async fn open(&self) -> io::Result<Context> {
+        let slave = Slave(self.unit);
+
+        match &self.transport {
+            Transport::Tcp { host, port } => {
+                let address = tokio::net::lookup_host((host.as_str(), *port))
+                    .await?
+                    .next()
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, format!("Unknown host {}", host))
+                    })?;
+                tcp::connect_slave(address, slave).await
+            }
+            Transport::Rtu { path, baud_rate } => {
+                let port = SerialStream::open(&tokio_serial::new(path, *baud_rate))?;
+                Ok(rtu::attach_slave(port, slave))
+            }
+        }
+    }
+
+    async fn read(&self, context: &mut Context) -> Result<f64, Error> {
+        let request = async {
+            match self.register {
+                Register::Holding => context.read_holding_registers(self.address, 1).await,
+                Register::Input => context.read_input_registers(self.address, 1).await,
+            }
+        };
+
+        let registers = time::timeout(TIMEOUT, request)
+            .await
+            .map_err(|_| Error::Device("No response from the Modbus device".to_string()))?
+            .map_err(|error| Error::Device(error.to_string()))?
+            .map_err(|exception| Error::Device(format!("Modbus exception: {}", exception)))?;
+        let value = registers
+            .first()
+            .copied()
+            .ok_or_else(|| Error::Device("Empty Modbus response".to_string()))?;
+
+        let value = if self.signed {
+            value as i16 as f64
+        } else {
+            value as f64
+        };
+        Ok(value * self.scale + self.offset)
+    }
+}
+
+/// Polls the register, attaching once it has been read. Like a detached
+/// Phidget, a failed read ends the connection so that it is retried.
+pub fn connect(config: Config) -> impl Straw<(), Event, Error> {
+    sipper(async move |mut events| {
+        let mut context = config.open().await?;
+        let temp = config.read(&mut context).await?;
+        events.send(Event::Attach).await;
+        events.send(Event::Change(TempData::new(temp))).await;
+
+        let mut interval = time::interval(Duration::from_millis(config.interval.max(50)));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            let temp = config.read(&mut context).await?;
+            events.send(Event::Change(TempData::new(temp))).await;
+        }
+    })
+}
@@ -0,0 +1,298 @@
+//! Publishes sensor readings and roast events to an MQTT broker.
+//!
+//! Topics, below the configured prefix and the name of the roaster:
+//!
+//! - `sensors/<name>/temperature`: `{"temp": 201.5, "at": "..."}` on every reading
+//! - `sensors/<name>/status`: `{"connected": false, "at": "..."}` on attach and detach
+//! - `roast`: `{"event": "start", "at": "...", ...}` for start, charge, stop,
+//!   resume, discard and marked events
+//!
+//! Messages are kept while the broker is unreachable and sent once it is
+//! back. Try it with `mosquitto -v` and `mosquitto_sub -t 'roaster/#' -v`.
+
+use chrono::Local;
+use iced::task::{Straw, sipper};
+use rumqttc::{AsyncClient, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{collections::VecDeque, fmt, time::Duration};
+use tokio::{
+    sync::mpsc,
+    time::{self, Instant},
+};
+
+use crate::{archive::EventKind, batch, sensor};
+
+/// Messages kept while the broker is unreachable, the oldest are dropped
+/// first.
+const BUFFER: usize = 10_000;
+const RETRY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Qos {
+    AtMostOnce,
+    AtLeastOnce,
+    ExactlyOnce,
+}
+
+impl Qos {
+    pub const ALL: [Qos; 3] = [Qos::AtMostOnce, Qos::AtLeastOnce, Qos::ExactlyOnce];
+}
+
+impl fmt::Display for Qos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Qos::AtMostOnce => write!(f, "0 – at most once"),
+            Qos::AtLeastOnce => write!(f, "1 – at least once"),
+            Qos::ExactlyOnce => write!(f, "2 – exactly once"),
+        }
+    }
+}
+
+impl From<Qos> for QoS {
+    fn from(qos: Qos) -> Self {
+        match qos {
+            Qos::AtMostOnce => QoS::AtMostOnce,
+            Qos::AtLeastOnce => QoS::AtLeastOnce,
+            Qos::ExactlyOnce => QoS::ExactlyOnce,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub prefix: String,
+    pub qos: Qos,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            prefix: "roaster".to_string(),
+            qos: Qos::AtLeastOnce,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Publication {
+    /// Topic below the prefix.
+    topic: String,
+    payload: Value,
+}
+
+/// Keeps sensor names from adding levels or wildcards to a topic.
+fn level(name: &str) -> String {
+    name.replace(['/', '+', '#'], "_")
+}
+
+/// Keeps a publication until the broker is reachable, dropping the oldest
+/// one once the buffer is full.
+fn enqueue(buffer: &mut VecDeque<Publication>, publication: Publication) {
+    buffer.push_back(publication);
+    if buffer.len() > BUFFER {
+        buffer.pop_front();
+    }
+}
+
+fn now() -> String {
+    Local::now().to_rfc3339()
+}
+
+impl Publication {
+    /// Moves the publication below the topic of a roaster.
+    pub fn on(self, machine: &str) -> Self {
+        Publication {
+            topic: format!("{}/{}", level(machine), self.topic),
+            ..self
+        }
+    }
+
+    pub fn sensor(name: &str, event: &sensor::Event) -> Self {
+        match event {
+            sensor::Event::Change(temp_data) => Publication {
+                topic: format!("sensors/{}/temperature", level(name)),
+                payload: json!({ "temp": temp_data.temp, "at": now() }),
+            },
+            sensor::Event::Attach => Publication::connection(name, true),
+            sensor::Event::Detach => Publication::connection(name, false),
+        }
+    }
+
+    pub fn connection(name: &str, connected: bool) -> Self {
+        Publication {
+            topic: format!("sensors/{}/status", level(name)),
+            payload: json!({ "connected": connected, "at": now() }),
+        }
+    }
+
+    pub fn started(batch: &batch::Start) -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({
+                "event": "start",
+                "at": now(),
+                "recipe": batch.recipe,
+                "green_weight": batch.green_weight,
+                "operator": batch.operator,
+                "machine": batch.machine,
+                "order": batch.order,
+            }),
+        }
+    }
+
+    pub fn charged() -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({ "event": "charge", "at": now() }),
+        }
+    }
+
+    /// `elapsed` is in seconds since charge.
+    pub fn marked(kind: EventKind, elapsed: f32) -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({ "event": kind.key(), "at": now(), "elapsed": elapsed }),
+        }
+    }
+
+    pub fn stopped(elapsed: f32) -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({ "event": "stop", "at": now(), "elapsed": elapsed }),
+        }
+    }
+
+    pub fn resumed(elapsed: f32) -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({ "event": "resume", "at": now(), "elapsed": elapsed }),
+        }
+    }
+
+    pub fn discarded() -> Self {
+        Publication {
+            topic: "roast".to_string(),
+            payload: json!({ "event": "discard", "at": now() }),
+        }
+    }
+}
+
+/// Publishes until the task is aborted or the application drops the sender,
+/// reporting the connection errors and `None` once connected again.
+pub fn publish(
+    config: Config,
+    mut publications: mpsc::UnboundedReceiver<Publication>,
+) -> impl Straw<(), Option<String>, String> {
+    sipper(async move |mut status| {
+        let host = config.host.trim();
+        if host.is_empty() {
+            return Err("The broker host is missing".to_string());
+        }
+
+        let mut options = MqttOptions::new(
+            format!("cambio-torrefaction-{:08x}", fastrand::u32(..)),
+            host,
+            config.port,
+        );
+        options.set_keep_alive(Duration::from_secs(30));
+        let (client, mut eventloop) = AsyncClient::new(options, 100);
+
+        let prefix = config.prefix.trim_end_matches('/');
+        let mut buffer = VecDeque::new();
+        let mut connected = false;
+        // Publications are still buffered while waiting to reconnect.
+        let mut retry_at: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                publication = publications.recv() => {
+                    let Some(publication) = publication else {
+                        return Ok(());
+                    };
+                    enqueue(&mut buffer, publication);
+                }
+                _ = time::sleep_until(retry_at.unwrap_or_else(Instant::now)), if retry_at.is_some() => {
+                    retry_at = None;
+                }
+                event = eventloop.poll(), if retry_at.is_none() => match event {
+                    Ok(rumqttc::Event::Incoming(Packet::ConnAck(_))) => {
+                        connected = true;
+                        status.send(None).await;
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        connected = false;
+                        retry_at = Some(Instant::now() + RETRY);
+                        status.send(Some(format!("The broker is unreachable: {}", error))).await;
+                    }
+                }
+            }
+
+            while connected {
+                let Some(publication) = buffer.pop_front() else {
+                    break;
+                };
+                let topic = format!("{}/{}", prefix, publication.topic);
+                let Ok(payload) = serde_json::to_vec(&publication.payload) else {
+                    continue;
+                };
+                // The client queue is full: keep the rest until it drains.
+                if client
+                    .try_publish(topic, config.qos.into(), false, payload)
+                    .is_err()
+                {
+                    buffer.push_front(publication);
+                    break;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_stay_a_single_level() {
+        assert_eq!(level("Bean probe"), "Bean probe");
+        assert_eq!(level("bean/exhaust"), "bean_exhaust");
+        assert_eq!(level("+#/"), "___");
+
+        let publication = Publication::connection("drum/+", true);
+        assert_eq!(publication.topic, "sensors/drum__/status");
+    }
+
+    #[test]
+    fn publications_move_below_the_roaster() {
+        let publication = Publication::charged().on("Giesen W6/2");
+        assert_eq!(publication.topic, "Giesen W6_2/roast");
+        assert_eq!(publication.payload["event"], "charge");
+
+        let publication = Publication::connection("bean", false).on("#1");
+        assert_eq!(publication.topic, "_1/sensors/bean/status");
+        assert_eq!(publication.payload["connected"], false);
+    }
+
+    #[test]
+    fn full_buffer_drops_the_oldest() {
+        let mut buffer = VecDeque::new();
+        for elapsed in 0..BUFFER + 2 {
+            enqueue(&mut buffer, Publication::stopped(elapsed as f32));
+        }
+
+        assert_eq!(buffer.len(), BUFFER);
+        assert_eq!(buffer.front().unwrap().payload["elapsed"], 2.0);
+        assert_eq!(
+            buffer.back().unwrap().payload["elapsed"],
+            (BUFFER + 1) as f64
+        );
+    }
+}
@@ -5,6 +5,8 @@ use std::{error::Error, fs, path::PathBuf, sync::Arc};
 use iced::{Theme, theme::Custom};
 use serde::{Deserialize, Serialize};
 
+use crate::{mqtt, sensor, server};
+
 pub static PROJECT_DIRS: Lazy<ProjectDirs> =
     Lazy::new(|| ProjectDirs::from("org", "cambio", "torrefaction").unwrap());
 
@@ -12,6 +14,15 @@ pub static PROJECT_DIRS: Lazy<ProjectDirs> =
 pub struct Preferences {
     #[serde(with = "ThemeDef")]
     pub theme: Theme,
+    #[serde(default = "sensor::Config::defaults")]
+    pub sensors: Vec<sensor::Config>,
+    /// Names of the roasters, each with its own sensors and roast.
+    #[serde(default = "default_machines")]
+    pub machines: Vec<String>,
+    #[serde(default)]
+    pub server: server::Config,
+    #[serde(default)]
+    pub mqtt: mqtt::Config,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -43,10 +54,18 @@ enum ThemeDef {
     Custom(Arc<Custom>),
 }
 
+fn default_machines() -> Vec<String> {
+    vec!["Roaster".to_string()]
+}
+
 impl Default for Preferences {
     fn default() -> Self {
         Preferences {
             theme: Theme::TokyoNight,
+            sensors: sensor::Config::defaults(),
+            machines: default_machines(),
+            server: server::Config::default(),
+            mqtt: mqtt::Config::default(),
         }
     }
 }
@@ -0,0 +1,355 @@
+use chrono::{Local, NaiveDate};
+use iced::{
+    Alignment, Element,
+    Length::Fill,
+    widget::{
+        button, column, container, horizontal_space, pick_list, row, scrollable, text, text_input,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, io, path::PathBuf};
+
+use crate::{data, inventory::Lot, preferences::PROJECT_DIRS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    Planned,
+    Active,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedBatch {
+    pub id: u64,
+    pub date: NaiveDate,
+    pub lot: Option<u64>,
+    pub recipe: Option<String>,
+    /// Target green weight in kg.
+    pub target_weight: f32,
+    pub order: String,
+    pub status: Status,
+    /// Green weight actually roasted, once done.
+    pub roasted: Option<f32>,
+}
+
+#[derive(Debug)]
+pub struct Production {
+    batches: Vec<PlannedBatch>,
+    day: NaiveDate,
+    recipes: Vec<String>,
+    lot: Option<u64>,
+    recipe: Option<String>,
+    target_weight: String,
+    order: String,
+    error: Option<String>,
+    /// Why the plan file could not be read, it is then never saved over.
+    load_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PreviousDay,
+    NextDay,
+    Today,
+    LotSelected(Lot),
+    RecipeSelected(String),
+    TargetWeightChanged(String),
+    OrderChanged(String),
+    Add,
+    MoveUp(u64),
+    MoveDown(u64),
+    Remove(u64),
+    Load(u64),
+}
+
+impl Production {
+    pub fn new(batches: Vec<PlannedBatch>) -> Self {
+        Production {
+            batches,
+            day: Local::now().date_naive(),
+            recipes: data::recipes()
+                .iter()
+                .map(|recipe| recipe.name().clone())
+                .collect(),
+            lot: None,
+            recipe: None,
+            target_weight: String::new(),
+            order: String::new(),
+            error: None,
+            load_error: None,
+        }
+    }
+
+    fn data_file() -> PathBuf {
+        let mut path = PROJECT_DIRS.data_dir().join("_").to_path_buf();
+        path.set_file_name("production.json");
+        path
+    }
+
+    fn read() -> Result<Vec<PlannedBatch>, Box<dyn Error>> {
+        match fs::read_to_string(Self::data_file()) {
+            Ok(string) => Ok(serde_json::from_str(&string)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Loads the plan, starting empty only when there is no plan yet.
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(batches) => Production::new(batches),
+            Err(error) => Production {
+                load_error: Some(format!(
+                    "{} could not be read, changes won't be saved: {}",
+                    Self::data_file().display(),
+                    error
+                )),
+                ..Production::new(Vec::new())
+            },
+        }
+    }
+
+    fn save(&mut self) {
+        if self.load_error.is_some() {
+            self.error =
+                Some("The production plan is left untouched as it could not be read".to_string());
+            return;
+        }
+
+        let result = (|| -> Result<(), Box<dyn Error>> {
+            let path = Self::data_file();
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(&self.batches)?)?;
+            Ok(())
+        })();
+
+        self.error = result.err().map(|error| error.to_string());
+    }
+
+    /// Marks `id` as the batch being roasted and returns it for preloading.
+    pub fn activate(&mut self, id: u64) -> Option<PlannedBatch> {
+        for batch in &mut self.batches {
+            if batch.status == Status::Active {
+                batch.status = Status::Planned;
+            }
+        }
+
+        let batch = self.batches.iter_mut().find(|batch| batch.id == id)?;
+        batch.status = Status::Active;
+        let batch = batch.clone();
+        self.save();
+
+        Some(batch)
+    }
+
+    /// Completes the planned batch `id` and activates the next planned one
+    /// of the same day, if any.
+    pub fn complete(&mut self, id: u64, green_weight: f32) -> Option<PlannedBatch> {
+        let batch = self
+            .batches
+            .iter_mut()
+            .find(|batch| batch.id == id && batch.status != Status::Done)?;
+        batch.status = Status::Done;
+        batch.roasted = Some(green_weight);
+        let day = batch.date;
+        self.save();
+
+        let next = self
+            .batches
+            .iter()
+            .find(|batch| batch.date == day && batch.status == Status::Planned)?
+            .id;
+        self.activate(next)
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<PlannedBatch> {
+        match message {
+            Message::PreviousDay => self.day = self.day.pred_opt().unwrap_or(self.day),
+            Message::NextDay => self.day = self.day.succ_opt().unwrap_or(self.day),
+            Message::Today => self.day = Local::now().date_naive(),
+            Message::LotSelected(lot) => self.lot = Some(lot.id),
+            Message::RecipeSelected(recipe) => self.recipe = Some(recipe),
+            Message::TargetWeightChanged(weight) => self.target_weight = weight,
+            Message::OrderChanged(order) => self.order = order,
+            Message::Add => match self.target_weight.trim().parse::<f32>() {
+                Ok(target_weight) if target_weight > 0.0 => {
+                    self.error = None;
+                    let id = self.batches.iter().map(|batch| batch.id).max().unwrap_or(0) + 1;
+                    self.batches.push(PlannedBatch {
+                        id,
+                        date: self.day,
+                        lot: self.lot,
+                        recipe: self.recipe.clone(),
+                        target_weight,
+                        order: self.order.trim().to_string(),
+                        status: Status::Planned,
+                        roasted: None,
+                    });
+                    self.target_weight.clear();
+                    self.order.clear();
+                    self.save();
+                }
+                _ => self.error = Some("Target weight must be a positive number".to_string()),
+            },
+            Message::MoveUp(id) | Message::MoveDown(id) => {
+                let day: Vec<usize> = (0..self.batches.len())
+                    .filter(|i| self.batches[*i].date == self.day)
+                    .collect();
+
+                if let Some(position) = day.iter().position(|i| self.batches[*i].id == id) {
+                    let other = match message {
+                        Message::MoveUp(_) => position.checked_sub(1),
+                        _ => Some(position + 1).filter(|other| *other < day.len()),
+                    };
+                    if let Some(other) = other {
+                        self.batches.swap(day[position], day[other]);
+                        self.save();
+                    }
+                }
+            }
+            Message::Remove(id) => {
+                self.batches.retain(|batch| batch.id != id);
+                self.save();
+            }
+            Message::Load(id) => return self.activate(id),
+        }
+
+        None
+    }
+
+    pub fn view<'a>(&'a self, lots: &'a [Lot]) -> Element<'a, Message> {
+        let title = column![text("Production").size(30)].push_maybe(
+            self.load_error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        );
+
+        let header = row![
+            button("<").on_press(Message::PreviousDay),
+            text(self.day.format("%A %d %B %Y").to_string()).size(20),
+            button(">").on_press(Message::NextDay),
+            horizontal_space(),
+            button("Today")
+                .on_press(Message::Today)
+                .style(button::secondary),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        let day: Vec<&PlannedBatch> = self
+            .batches
+            .iter()
+            .filter(|batch| batch.date == self.day)
+            .collect();
+
+        let done = day.iter().filter(|batch| batch.status == Status::Done);
+        let remaining = day.iter().filter(|batch| batch.status != Status::Done);
+        let summary = text(format!(
+            "Completed {} batches ({:.1} kg), remaining {} batches ({:.1} kg)",
+            done.clone().count(),
+            done.map(|batch| batch.roasted.unwrap_or(batch.target_weight))
+                .sum::<f32>(),
+            remaining.clone().count(),
+            remaining.map(|batch| batch.target_weight).sum::<f32>(),
+        ));
+
+        let queue = scrollable(
+            column(day.into_iter().map(|batch| {
+                let lot = batch
+                    .lot
+                    .and_then(|id| lots.iter().find(|lot| lot.id == id))
+                    .map_or("No lot".to_string(), |lot| {
+                        format!("{} – {}", lot.origin, lot.farm)
+                    });
+                let status = match batch.status {
+                    Status::Planned => text("Planned").style(text::secondary),
+                    Status::Active => text("Roasting").style(text::primary),
+                    Status::Done => text("Done").style(text::success),
+                };
+
+                let mut actions = row![].spacing(5);
+                if batch.status == Status::Planned {
+                    actions = actions
+                        .push(button("↑").on_press(Message::MoveUp(batch.id)))
+                        .push(button("↓").on_press(Message::MoveDown(batch.id)))
+                        .push(
+                            button("Load")
+                                .on_press(Message::Load(batch.id))
+                                .style(button::success),
+                        );
+                }
+                if batch.status != Status::Active {
+                    actions = actions.push(
+                        button("Remove")
+                            .on_press(Message::Remove(batch.id))
+                            .style(button::danger),
+                    );
+                }
+
+                row![
+                    column![
+                        text(format!(
+                            "{} – {:.1} kg",
+                            batch.recipe.as_deref().unwrap_or("No recipe"),
+                            batch.target_weight
+                        )),
+                        text(format!("{} · {}", lot, batch.order))
+                            .size(14)
+                            .style(text::secondary),
+                    ],
+                    horizontal_space(),
+                    status,
+                    actions,
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(10),
+        )
+        .height(Fill);
+
+        let lot = self.lot.and_then(|id| lots.iter().find(|lot| lot.id == id));
+        let form = column![
+            row![
+                pick_list(lots, lot, Message::LotSelected)
+                    .placeholder("Green lot...")
+                    .width(Fill),
+                pick_list(
+                    self.recipes.as_slice(),
+                    self.recipe.as_ref(),
+                    Message::RecipeSelected
+                )
+                .placeholder("Recipe...")
+                .width(Fill),
+            ]
+            .spacing(10),
+            row![
+                text_input("Target weight (kg)", &self.target_weight)
+                    .on_input(Message::TargetWeightChanged),
+                text_input("Customer order", &self.order).on_input(Message::OrderChanged),
+                button("Add batch")
+                    .on_press(Message::Add)
+                    .style(button::success),
+            ]
+            .spacing(10),
+        ]
+        .push_maybe(
+            self.error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10);
+
+        container(
+            column![title, header, summary, queue, form]
+                .max_width(800)
+                .spacing(20),
+        )
+        .center_x(Fill)
+        .padding(20)
+        .into()
+    }
+}
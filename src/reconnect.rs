@@ -0,0 +1,151 @@
+use chrono::{DateTime, Local};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::preferences::PROJECT_DIRS;
+
+const BASE_DELAY: Duration = Duration::from_millis(250);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+const JITTER: f64 = 0.2;
+const HISTORY_LEN: usize = 20;
+
+#[derive(Debug, Clone, Default)]
+pub struct Backoff {
+    attempt: u32,
+    next_at: Option<Instant>,
+    history: Vec<(DateTime<Local>, String)>,
+}
+
+impl Backoff {
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn next_at(&self) -> Option<Instant> {
+        self.next_at
+    }
+
+    pub fn history(&self) -> &[(DateTime<Local>, String)] {
+        &self.history
+    }
+
+    /// Exponential delay for the upcoming attempt, randomized by ±20 % so
+    /// that sensors on the same hub don't retry in lockstep.
+    fn delay(&self) -> Duration {
+        let exponential = BASE_DELAY.saturating_mul(1 << self.attempt.min(16));
+        let jitter = 1.0 + JITTER * (fastrand::f64() * 2.0 - 1.0);
+
+        exponential.min(MAX_DELAY).mul_f64(jitter)
+    }
+
+    /// Schedules the next attempt after a failed or lost connection.
+    pub fn schedule(&mut self, now: Instant) {
+        self.next_at = Some(now + self.delay());
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next_at.is_some_and(|next_at| now >= next_at)
+    }
+
+    /// Marks the start of an attempt, returning its number.
+    pub fn start_attempt(&mut self) -> u32 {
+        self.attempt += 1;
+        self.next_at = None;
+        self.attempt
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+        self.next_at = None;
+    }
+
+    /// Remembers a connection event and appends it to the sensor log.
+    pub fn log(&mut self, sensor: &str, message: String) {
+        let now = Local::now();
+
+        if let Some(parent) = log_file().parent() {
+            fs::create_dir_all(parent).ok();
+        }
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file())
+        {
+            writeln!(file, "{} [{}] {}", now.to_rfc3339(), sensor, message).ok();
+        }
+
+        if self.history.len() == HISTORY_LEN {
+            self.history.remove(0);
+        }
+        self.history.push((now, message));
+    }
+}
+
+fn log_file() -> PathBuf {
+    let mut path = PROJECT_DIRS.data_dir().join("_").to_path_buf();
+    path.set_file_name("sensors.log");
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(delay: Duration) -> (Duration, Duration) {
+        (delay.mul_f64(1.0 - JITTER), delay.mul_f64(1.0 + JITTER))
+    }
+
+    fn after(attempts: u32) -> Backoff {
+        let mut backoff = Backoff::default();
+        for _ in 0..attempts {
+            backoff.start_attempt();
+        }
+        backoff
+    }
+
+    #[test]
+    fn delay_doubles_with_each_attempt() {
+        for attempts in 0..6 {
+            let (min, max) = bounds(BASE_DELAY * (1 << attempts));
+            let delay = after(attempts).delay();
+            assert!(min <= delay && delay <= max, "{attempts}: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        let (min, max) = bounds(MAX_DELAY);
+        for attempts in [8, 16, 40] {
+            let delay = after(attempts).delay();
+            assert!(min <= delay && delay <= max, "{attempts}: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let backoff = after(2);
+        let (min, max) = bounds(BASE_DELAY * 4);
+        let delays: Vec<Duration> = (0..1000).map(|_| backoff.delay()).collect();
+
+        assert!(delays.iter().all(|delay| min <= *delay && *delay <= max));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+
+    #[test]
+    fn reset_restarts_from_the_base_delay() {
+        let mut backoff = after(10);
+        let now = Instant::now();
+        backoff.schedule(now);
+        assert!(!backoff.is_due(now));
+
+        backoff.reset();
+        assert!(!backoff.is_due(now + MAX_DELAY * 2));
+        let (min, max) = bounds(BASE_DELAY);
+        let delay = backoff.delay();
+        assert!(min <= delay && delay <= max);
+    }
+}
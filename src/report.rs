@@ -0,0 +1,515 @@
+use iced::{Color, Point, Size, Theme, Vector};
+use resvg::usvg::fontdb;
+use std::{
+    error::Error,
+    fmt, fs,
+    path::PathBuf,
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    archive::{Entry, RawRoastData},
+    data::{self, Checkpoint},
+    format::{duration, point},
+    roast::{Mark, Roast},
+};
+
+/// A4 in points.
+const PAGE: Size = Size::new(595.0, 842.0);
+const MARGIN: f32 = 40.0;
+const CHART_HEIGHT: f32 = 300.0;
+/// The chart gives up room to long recipes down to this height, then the
+/// steps are packed closer together.
+const MIN_CHART_HEIGHT: f32 = 160.0;
+const LINE_HEIGHT: f32 = 15.0;
+/// Recipe steps are listed in columns under the summary.
+const STEP_COLUMNS: usize = 2;
+const STEP_SIZE: f32 = 9.0;
+
+/// System fonts, loaded once for every image and PDF.
+static FONTS: LazyLock<Arc<fontdb::Database>> = LazyLock::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    Arc::new(fonts)
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Svg,
+    Pdf,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Svg, Format::Pdf];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Pdf => "pdf",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension().to_uppercase())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn rgb(color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+    format!("rgb({},{},{})", r, g, b)
+}
+
+fn points(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{:.1},{:.1}", point.x, point.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Writes marks as an SVG document of the given size.
+pub fn svg(size: Size, background: Color, marks: &[Mark]) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        rgb(background),
+        w = size.width,
+        h = size.height,
+    );
+
+    for mark in marks {
+        let element = match mark {
+            Mark::Line {
+                points: line,
+                color,
+                width,
+            } => format!(
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\"/>",
+                points(line),
+                rgb(*color),
+                color.a,
+                width
+            ),
+            Mark::Area {
+                points: area,
+                color,
+            } => format!(
+                "<polygon points=\"{}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+                points(area),
+                rgb(*color),
+                color.a
+            ),
+            Mark::Label {
+                position,
+                content,
+                color,
+                size,
+            } => format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\" fill-opacity=\"{}\" dominant-baseline=\"hanging\">{}</text>",
+                position.x,
+                position.y,
+                size,
+                rgb(*color),
+                color.a,
+                escape(content)
+            ),
+        };
+        svg.push_str(&element);
+        svg.push('\n');
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Converts an SVG document to a single page PDF.
+pub fn pdf(svg: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let options = svg2pdf::usvg::Options {
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = svg2pdf::usvg::Tree::from_str(svg, &options)?;
+
+    svg2pdf::to_pdf(
+        &tree,
+        svg2pdf::ConversionOptions::default(),
+        svg2pdf::PageOptions::default(),
+    )
+    .map_err(|error| error.to_string().into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 4] = [
+        Resolution::new(1280, 720),
+        Resolution::new(1920, 1080),
+        Resolution::new(2560, 1440),
+        Resolution::new(3840, 2160),
+    ];
+
+    pub const fn new(width: u32, height: u32) -> Self {
+        Resolution { width, height }
+    }
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Resolution::new(1920, 1080)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}×{}", self.width, self.height)
+    }
+}
+
+/// The roast chart alone, with axes and legend, in the colors of `theme`.
+pub fn chart(roast: &RawRoastData, size: Size, theme: &Theme) -> String {
+    let chart = Roast::from_saved(roast, 0.0);
+    let palette = theme.palette();
+    let marks: Vec<Mark> = chart
+        .marks(size, None, true)
+        .into_iter()
+        .chain(chart.axes(size, palette.text))
+        .chain(chart.legend(size, palette.text))
+        .collect();
+
+    svg(size, palette.background, &marks)
+}
+
+/// Rasterizes the roast chart, without needing a window.
+pub fn png(
+    roast: &RawRoastData,
+    resolution: Resolution,
+    theme: &Theme,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    use resvg::{tiny_skia, usvg};
+
+    let size = Size::new(resolution.width as f32, resolution.height as f32);
+    let options = usvg::Options {
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(&chart(roast, size, theme), &options)?;
+
+    let mut pixmap =
+        tiny_skia::Pixmap::new(resolution.width, resolution.height).ok_or("Invalid image size")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    Ok(pixmap.encode_png()?)
+}
+
+/// Writes the chart image next to the saved roast.
+pub fn export_png(
+    entry: &Entry,
+    resolution: Resolution,
+    theme: &Theme,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let path = entry.path.with_extension("png");
+    fs::write(&path, png(&entry.roast, resolution, theme)?)?;
+    Ok(path)
+}
+
+fn translate(mark: Mark, offset: Vector) -> Mark {
+    match mark {
+        Mark::Line {
+            points,
+            color,
+            width,
+        } => Mark::Line {
+            points: points.into_iter().map(|point| point + offset).collect(),
+            color,
+            width,
+        },
+        Mark::Area { points, color } => Mark::Area {
+            points: points.into_iter().map(|point| point + offset).collect(),
+            color,
+        },
+        Mark::Label {
+            position,
+            content,
+            color,
+            size,
+        } => Mark::Label {
+            position: position + offset,
+            content,
+            color,
+            size,
+        },
+    }
+}
+
+/// Time at which a recipe checkpoint was reached during the roast.
+fn triggered(roast: &RawRoastData, checkpoint: &Checkpoint) -> Option<f32> {
+    let total_time = roast.metrics().total_time;
+    match checkpoint {
+        Checkpoint::Time(time) => Some(time.as_secs_f32()).filter(|time| *time <= total_time),
+        Checkpoint::Temp(temp) => {
+            let (turning_point, _) = roast.metrics().turning_point?;
+            roast
+                .bean()?
+                .points
+                .iter()
+                .find(|(t, time)| *time >= turning_point && t >= temp)
+                .map(|(_, time)| *time)
+        }
+    }
+}
+
+/// Height of a line of text of `size`.
+fn advance(size: f32) -> f32 {
+    size.max(LINE_HEIGHT) + 2.0
+}
+
+/// Height of the chart and of a row of steps so that `rows` rows fit in
+/// `room`, the height left to both.
+fn fit(room: f32, rows: usize) -> (f32, f32) {
+    let line = STEP_SIZE + 2.0;
+    let chart = (room - rows as f32 * line).clamp(MIN_CHART_HEIGHT, CHART_HEIGHT);
+    let line = match rows {
+        0 => line,
+        rows => line.min((room - chart) / rows as f32),
+    };
+    (chart, line)
+}
+
+/// Lays out a full roast sheet on one page: metadata, chart, metrics and
+/// recipe steps.
+pub fn page(roast: &RawRoastData) -> Vec<Mark> {
+    let black = Color::BLACK;
+    let mut y = MARGIN;
+    let mut marks = Vec::new();
+    let line = |marks: &mut Vec<Mark>, y: &mut f32, content: String, size: f32| {
+        marks.push(Mark::Label {
+            position: Point::new(MARGIN, *y),
+            content,
+            color: black,
+            size,
+        });
+        *y += advance(size);
+    };
+
+    let start = &roast.start;
+    line(&mut marks, &mut y, "Roast report".to_string(), 22.0);
+    line(
+        &mut marks,
+        &mut y,
+        format!(
+            "{} – {}",
+            start.started_at.format("%d-%m-%Y %H:%M"),
+            start.recipe.as_deref().unwrap_or("No recipe")
+        ),
+        14.0,
+    );
+    y += 6.0;
+
+    let mut metadata = vec![
+        format!(
+            "Lot: {}",
+            start.lot.map_or("–".to_string(), |lot| format!("#{}", lot))
+        ),
+        format!("Green weight: {:.2} kg", start.green_weight),
+        format!(
+            "Operator: {}  ·  Machine: {}",
+            start.operator, start.machine
+        ),
+    ];
+    if let Some(order) = &start.order {
+        metadata.push(format!("Order: {}", order));
+    }
+    if let (Some(temp), Some(humidity)) = (start.ambient_temp, start.humidity) {
+        metadata.push(format!("Ambient: {:.1} °C, {:.0} % RH", temp, humidity));
+    }
+    if let Some(end) = &roast.end {
+        metadata.push(format!(
+            "Roasted weight: {:.2} kg  ·  Weight loss: {:.1} %",
+            end.roasted_weight,
+            roast.weight_loss.unwrap_or_default()
+        ));
+        if let Some(color) = end.color {
+            metadata.push(format!("Color: {:.1}", color));
+        }
+        if !end.notes.is_empty() {
+            metadata.push(format!("Notes: {}", end.notes));
+        }
+    }
+    for content in metadata {
+        line(&mut marks, &mut y, content, 11.0);
+    }
+    y += 10.0;
+
+    let chart = Roast::from_saved(roast, 0.0);
+    let metrics = roast.metrics();
+    let summary: Vec<String> = [
+        format!("Turning point: {}", point(metrics.turning_point)),
+        format!("First crack: {}", point(metrics.first_crack)),
+        format!("Drop: {}", point(metrics.drop)),
+        format!(
+            "Development time ratio: {}",
+            metrics
+                .development
+                .map_or("–".to_string(), |dtr| format!("{:.1} %", dtr))
+        ),
+        format!("Total time: {}", duration(metrics.total_time)),
+    ]
+    .into_iter()
+    .chain(
+        chart
+            .phases()
+            .into_iter()
+            .map(|(name, from, to)| format!("{}: {}", name, duration(to - from))),
+    )
+    .collect();
+    let steps: Vec<String> = data::recipes()
+        .into_iter()
+        .find(|recipe| Some(recipe.name()) == start.recipe.as_ref())
+        .map(|recipe| {
+            recipe
+                .steps()
+                .iter()
+                .map(|step| {
+                    let checkpoint = match step.checkpoint() {
+                        Checkpoint::Time(time) => duration(time.as_secs_f32()),
+                        Checkpoint::Temp(temp) => format!("{} °C", temp),
+                    };
+                    let actual = triggered(roast, step.checkpoint())
+                        .map_or("not reached".to_string(), |time| {
+                            format!("at {}", duration(time))
+                        });
+                    format!("{}  {}  ({})", checkpoint, step.step_type(), actual)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let rows = steps.len().div_ceil(STEP_COLUMNS);
+    let below_chart = 16.0
+        + advance(14.0)
+        + summary.len() as f32 * advance(11.0)
+        + 10.0
+        + if steps.is_empty() { 0.0 } else { advance(14.0) };
+    let (chart_height, step_line) = fit(PAGE.height - MARGIN - y - below_chart, rows);
+
+    let size = Size::new(PAGE.width - 2.0 * MARGIN, chart_height);
+    let offset = Vector::new(MARGIN, y);
+    marks.extend(
+        chart
+            .marks(size, None, true)
+            .into_iter()
+            .chain(chart.axes(size, black))
+            .chain(chart.legend(size, black))
+            .map(|mark| translate(mark, offset)),
+    );
+    y += chart_height + 16.0;
+
+    line(&mut marks, &mut y, "Summary".to_string(), 14.0);
+    for content in summary {
+        line(&mut marks, &mut y, content, 11.0);
+    }
+    y += 10.0;
+
+    if !steps.is_empty() {
+        line(&mut marks, &mut y, "Recipe steps".to_string(), 14.0);
+        let column_width = (PAGE.width - 2.0 * MARGIN) / STEP_COLUMNS as f32;
+        for (i, content) in steps.into_iter().enumerate() {
+            marks.push(Mark::Label {
+                position: Point::new(
+                    MARGIN + (i / rows) as f32 * column_width,
+                    y + (i % rows) as f32 * step_line,
+                ),
+                content,
+                color: black,
+                size: STEP_SIZE.min(step_line - 2.0).max(1.0),
+            });
+        }
+    }
+
+    marks
+}
+
+pub fn render(roast: &RawRoastData, format: Format) -> Result<Vec<u8>, Box<dyn Error>> {
+    let svg = svg(PAGE, Color::WHITE, &page(roast));
+    match format {
+        Format::Svg => Ok(svg.into_bytes()),
+        Format::Pdf => pdf(&svg),
+    }
+}
+
+/// Writes the report next to the saved roast.
+pub fn export(entry: &Entry, format: Format) -> Result<PathBuf, Box<dyn Error>> {
+    let path = entry.path.with_extension(format.extension());
+    fs::write(&path, render(&entry.roast, format)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chart_gives_up_room_to_steps() {
+        let (chart, line) = fit(400.0, 4);
+        assert_eq!(chart, CHART_HEIGHT);
+        assert_eq!(line, STEP_SIZE + 2.0);
+
+        let (chart, line) = fit(400.0, 13);
+        assert!(chart < CHART_HEIGHT && chart >= MIN_CHART_HEIGHT);
+        assert_eq!(line, STEP_SIZE + 2.0);
+        assert!(chart + 13.0 * line <= 400.0);
+
+        let (chart, line) = fit(250.0, 20);
+        assert_eq!(chart, MIN_CHART_HEIGHT);
+        assert!(chart + 20.0 * line <= 250.0 + 0.01);
+    }
+
+    #[test]
+    fn recipe_steps_stay_on_the_page() {
+        let recipe = data::recipes()
+            .into_iter()
+            .max_by_key(|recipe| recipe.steps().len())
+            .unwrap();
+        let roast = RawRoastData {
+            start: crate::batch::Start {
+                recipe: Some(recipe.name().clone()),
+                order: Some("Order 12".to_string()),
+                ambient_temp: Some(21.0),
+                humidity: Some(45.0),
+                ..Default::default()
+            },
+            end: Some(crate::batch::End {
+                roasted_weight: 10.0,
+                color: Some(55.0),
+                notes: "Sweet".to_string(),
+            }),
+            weight_loss: Some(15.0),
+            events: Vec::new(),
+            data: Vec::new(),
+            preheat: Vec::new(),
+        };
+
+        for mark in page(&roast) {
+            let bottom = match mark {
+                Mark::Label { position, size, .. } => position.y + size,
+                Mark::Line { points, .. } | Mark::Area { points, .. } => {
+                    points.iter().map(|point| point.y).fold(0.0, f32::max)
+                }
+            };
+            assert!(bottom <= PAGE.height - MARGIN + 0.01, "{bottom}");
+        }
+    }
+}
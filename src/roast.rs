@@ -0,0 +1,801 @@
+use iced::{
+    Color, Point, Rectangle, Renderer, Size, Theme, mouse,
+    widget::canvas::{self, Frame, Geometry, Path, Program, Stroke},
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    archive::{Event, EventKind, RawCurveData, RawRoastData},
+    batch,
+    data::{self, Checkpoint, Recipe, Step, StepType},
+    filter::Filter,
+    health::Health,
+    sensor::TempData,
+    stats::Envelope,
+};
+
+const ROR_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Default)]
+pub enum CurveFit {
+    #[default]
+    Normal,
+    Padding(f32, f32),
+    AlwaysFit(f32, f32),
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveSettings {
+    pub min: f32,
+    pub max: f32,
+    pub fit: CurveFit,
+}
+
+impl CurveSettings {
+    pub fn window(&self, min: f32, max: f32) -> (f32, f32) {
+        match self.fit {
+            CurveFit::Normal => (self.min, self.max),
+            CurveFit::Padding(pl, pr) => (self.min.min(min - pl), self.max.max(max + pr)),
+            CurveFit::AlwaysFit(pl, pr) => (min - pl, max + pr),
+        }
+    }
+
+    pub fn fit(window: (f32, f32), v: f32, size: f32) -> f32 {
+        (v - window.0) / (window.1 - window.0) * size
+    }
+
+    pub fn fit_flip(window: (f32, f32), v: f32, size: f32) -> f32 {
+        (1.0 - (v - window.0) / (window.1 - window.0)) * size
+    }
+
+    pub fn temperature() -> Self {
+        CurveSettings {
+            min: 0.0,
+            max: 230.0,
+            fit: CurveFit::Normal,
+        }
+    }
+
+    pub fn rate_of_rise() -> Self {
+        CurveSettings {
+            min: 0.0,
+            max: 30.0,
+            fit: CurveFit::Normal,
+        }
+    }
+
+    pub fn time() -> Self {
+        CurveSettings {
+            min: 0.0,
+            max: 17.0 * 60.0,
+            fit: CurveFit::Padding(0.0, 10.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoastCurve {
+    pub source_id: usize,
+    pub name: String,
+    pub color: Color,
+    pub settings: CurveSettings,
+    pub raw: Vec<TempData>,
+    pub points: Vec<TempData>,
+    pub filter: Filter,
+    pub health: Health,
+}
+
+impl RoastCurve {
+    pub fn new(
+        id: usize,
+        name: &str,
+        color: Color,
+        curve_settings: CurveSettings,
+        filter: Filter,
+    ) -> Self {
+        Self {
+            source_id: id,
+            name: name.to_string(),
+            color,
+            settings: curve_settings,
+            raw: Vec::new(),
+            points: Vec::new(),
+            filter,
+            health: Health::default(),
+        }
+    }
+
+    pub fn push(&mut self, temp_data: &TempData) {
+        self.health.record(temp_data);
+        self.raw.push(temp_data.clone());
+        if let Some(filtered) = self.filter.apply(temp_data) {
+            self.points.push(filtered);
+        }
+    }
+
+    /// Rate of rise in °C/min, as the slope over the last 30 seconds.
+    pub fn rate_of_rise(&self) -> RoastCurve {
+        let mut start = 0;
+        let points = self
+            .points
+            .iter()
+            .filter_map(|temp_data| {
+                while temp_data.time.duration_since(self.points[start].time) > ROR_WINDOW {
+                    start += 1;
+                }
+                let first = &self.points[start];
+                let span = temp_data.time.duration_since(first.time).as_secs_f64();
+
+                (span >= ROR_WINDOW.as_secs_f64() / 2.0).then(|| TempData {
+                    temp: (temp_data.temp - first.temp) / span * 60.0,
+                    time: temp_data.time,
+                })
+            })
+            .collect();
+
+        RoastCurve {
+            name: format!("{} RoR", self.name),
+            settings: CurveSettings::rate_of_rise(),
+            raw: Vec::new(),
+            points,
+            ..self.clone()
+        }
+    }
+
+    pub fn last_rate_of_rise(&self) -> Option<f64> {
+        let last = self.points.last()?;
+        let first = self
+            .points
+            .iter()
+            .find(|temp_data| last.time.duration_since(temp_data.time) <= ROR_WINDOW)?;
+        let span = last.time.duration_since(first.time).as_secs_f64();
+
+        (span > 0.0).then(|| (last.temp - first.temp) / span * 60.0)
+    }
+
+    /// The points from charge to drop, the earlier ones were recorded during
+    /// the pre-heat.
+    pub fn between(&self, start_time: Instant, end_time: Instant) -> &[TempData] {
+        between(&self.points, start_time, end_time)
+    }
+
+    pub fn points(
+        &self,
+        start_time: Instant,
+        last_time: Instant,
+        t_settings: &CurveSettings,
+        size: Size,
+    ) -> Vec<Point> {
+        let points = self.between(start_time, last_time);
+        let iter = points.iter().map(|p| p.temp as f32);
+        let min = iter.clone().reduce(f32::min).unwrap_or(0.);
+        let max = iter.reduce(f32::max).unwrap_or(0.);
+
+        let t_window = t_settings.window(0.0, last_time.duration_since(start_time).as_secs_f32());
+        let v_window = self.settings.window(min, max);
+
+        points
+            .iter()
+            .map(|temp_data| {
+                Point::new(
+                    CurveSettings::fit(
+                        t_window,
+                        temp_data.time.duration_since(start_time).as_secs_f32(),
+                        size.width,
+                    ),
+                    CurveSettings::fit_flip(v_window, temp_data.temp as f32, size.height),
+                )
+            })
+            .collect()
+    }
+}
+
+/// A drawing primitive, so the chart can be rendered on a canvas as well as
+/// into files.
+#[derive(Debug, Clone)]
+pub enum Mark {
+    Line {
+        points: Vec<Point>,
+        color: Color,
+        width: f32,
+    },
+    Area {
+        points: Vec<Point>,
+        color: Color,
+    },
+    Label {
+        position: Point,
+        content: String,
+        color: Color,
+        size: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Roast {
+    pub start_time: Instant,
+    pub last_time: Instant,
+    pub curves: Vec<RoastCurve>,
+    pub settings: CurveSettings,
+    pub events: Vec<(EventKind, Instant)>,
+    /// Whether the beans are in. Before charge, the curves are recorded from
+    /// `start_time`, which then moves to the charge.
+    pub charged: bool,
+    /// When the beans left the drum, the readings after it are not part of
+    /// the roast.
+    pub drop_time: Option<Instant>,
+    /// Spread of earlier roasts of the same recipe, drawn behind the curves.
+    pub envelope: Option<Envelope>,
+    /// The recipe followed, looked up once when the roast starts.
+    pub recipe: Option<Recipe>,
+    pub batch: batch::Start,
+    pub end: Option<batch::End>,
+}
+
+impl Roast {
+    pub fn new(
+        curves: Vec<RoastCurve>,
+        curve_settings: CurveSettings,
+        batch: batch::Start,
+    ) -> Self {
+        let now = Instant::now();
+        let recipe = data::recipes()
+            .into_iter()
+            .find(|recipe| Some(recipe.name()) == batch.recipe.as_ref());
+
+        Self {
+            start_time: now,
+            last_time: now,
+            curves,
+            settings: curve_settings,
+            events: Vec::new(),
+            charged: true,
+            drop_time: None,
+            envelope: None,
+            recipe,
+            batch,
+            end: None,
+        }
+    }
+
+    /// Rebuilds a saved roast so it can be drawn, shifted right by `offset`
+    /// seconds on the time axis.
+    pub fn from_saved(data: &RawRoastData, offset: f32) -> Self {
+        let start_time = Instant::now();
+        let base = start_time + Duration::from_secs_f32(offset.max(0.0));
+        let at = |time: f32| base + Duration::from_secs_f32(time.max(0.0));
+        let points = |points: &[(f32, f32)]| {
+            points
+                .iter()
+                .map(|(temp, time)| TempData {
+                    temp: *temp as f64,
+                    time: at(*time),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let curves: Vec<RoastCurve> = data
+            .data
+            .iter()
+            .map(|c| {
+                let [r, g, b] = c.color;
+                RoastCurve {
+                    points: points(&c.points),
+                    raw: points(&c.raw),
+                    ..RoastCurve::new(
+                        c.id,
+                        &c.name,
+                        Color::from_rgb(r, g, b),
+                        CurveSettings::temperature(),
+                        Filter::new(c.filter.clone()),
+                    )
+                }
+            })
+            .collect();
+
+        Self {
+            start_time,
+            last_time: curves
+                .iter()
+                .filter_map(|c| c.points.last())
+                .map(|temp_data| temp_data.time)
+                .max()
+                .unwrap_or(start_time),
+            curves,
+            settings: CurveSettings::time(),
+            events: data
+                .events
+                .iter()
+                .map(|event| (event.kind, at(event.time)))
+                .collect(),
+            charged: true,
+            drop_time: None,
+            envelope: None,
+            recipe: None,
+            batch: data.start.clone(),
+            end: data.end.clone(),
+        }
+    }
+
+    /// The bean probe is the first configured sensor.
+    pub fn bean(&self) -> Option<&RoastCurve> {
+        self.curves.first()
+    }
+
+    /// The drop, or the last reading while roasting.
+    pub fn end_time(&self) -> Instant {
+        self.drop_time.unwrap_or(self.last_time)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.end_time().duration_since(self.start_time)
+    }
+
+    /// Starts the timeline at the charge, the readings before it are kept as
+    /// the pre-heat.
+    pub fn charge(&mut self, at: Instant) {
+        self.start_time = at;
+        self.charged = true;
+    }
+
+    /// Moves the charge, no earlier than the first reading nor later than
+    /// the drop.
+    pub fn move_charge(&mut self, at: Instant) {
+        self.start_time = at.max(self.first_time()).min(self.end_time());
+    }
+
+    /// The first reading, before charge when pre-heating.
+    pub fn first_time(&self) -> Instant {
+        self.curves
+            .iter()
+            .filter_map(|curve| curve.raw.first())
+            .map(|temp_data| temp_data.time)
+            .fold(self.start_time, Instant::min)
+    }
+
+    /// Moves the drop, between the charge and the last reading.
+    pub fn move_drop(&mut self, at: Instant) {
+        self.drop_time = Some(at.max(self.start_time).min(self.last_time));
+    }
+
+    pub fn mark(&mut self, kind: EventKind) {
+        self.events.retain(|(k, _)| *k != kind);
+        self.events.push((kind, self.last_time));
+    }
+
+    /// The first step of the recipe whose checkpoint has not been reached
+    /// yet. Temperatures only count once the bean curve has turned.
+    pub fn next_step(&self) -> Option<&Step> {
+        let recipe = self.recipe.as_ref()?;
+        let elapsed = self.elapsed().as_secs_f32();
+        let bean = self
+            .bean()
+            .map(|bean| bean.between(self.start_time, self.end_time()))
+            .unwrap_or_default();
+        let turning_point = bean
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.temp.total_cmp(&b.temp))
+            .map_or(0, |(i, _)| i);
+        let highest = bean[turning_point.min(bean.len())..]
+            .iter()
+            .map(|temp_data| temp_data.temp as f32)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        recipe.steps().iter().find(|step| {
+            !matches!(step.step_type(), StepType::Start)
+                && match step.checkpoint() {
+                    Checkpoint::Time(time) => time.as_secs_f32() > elapsed,
+                    Checkpoint::Temp(temp) => *temp > highest,
+                }
+        })
+    }
+
+    /// Events with their time since charge.
+    pub fn saved_events(&self) -> Vec<Event> {
+        self.events
+            .iter()
+            .map(|(kind, time)| Event {
+                kind: *kind,
+                time: time.duration_since(self.start_time).as_secs_f32(),
+            })
+            .collect()
+    }
+
+    pub fn event(&self, kind: EventKind) -> Option<f32> {
+        self.events
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, time)| time.duration_since(self.start_time).as_secs_f32())
+    }
+
+    /// Drying, Maillard and development phases as `(name, from, to)` in
+    /// seconds, as far as the marked events allow.
+    pub fn phases(&self) -> Vec<(&'static str, f32, f32)> {
+        let end = self.elapsed().as_secs_f32();
+        let dry_end = self.event(EventKind::DryEnd);
+        let first_crack = self.event(EventKind::FirstCrack);
+
+        let mut phases = Vec::new();
+        if let Some(dry_end) = dry_end {
+            phases.push(("Drying", 0.0, dry_end));
+            phases.push(("Maillard", dry_end, first_crack.unwrap_or(end)));
+        }
+        if let Some(first_crack) = first_crack {
+            phases.push(("Development", first_crack, end));
+        }
+        phases
+    }
+
+    /// The phase the roast is in, drying until dry end is marked.
+    pub fn phase(&self) -> &'static str {
+        self.phases().last().map_or("Drying", |(name, _, _)| name)
+    }
+
+    fn time_window(&self) -> (f32, f32) {
+        self.settings.window(0.0, self.elapsed().as_secs_f32())
+    }
+
+    fn temp_window(&self) -> (f32, f32) {
+        self.bean()
+            .map_or(CurveSettings::temperature(), |bean| bean.settings.clone())
+            .window(0.0, 0.0)
+    }
+
+    fn x(&self, seconds: f32, size: Size) -> f32 {
+        CurveSettings::fit(self.time_window(), seconds, size.width)
+    }
+
+    fn y(&self, temp: f32, size: Size) -> f32 {
+        CurveSettings::fit_flip(self.temp_window(), temp, size.height)
+    }
+
+    /// The curves, events and phases of the roast, using `color` instead of
+    /// the sensor colors when given. Phases are only shaded in that case.
+    pub fn marks(&self, size: Size, color: Option<Color>, ror: bool) -> Vec<Mark> {
+        let mut marks = Vec::new();
+        let gray = Color::from_rgb(0.6, 0.6, 0.6);
+
+        if color.is_none() {
+            for (i, (name, from, to)) in self.phases().into_iter().enumerate() {
+                let (from, to) = (self.x(from, size), self.x(to, size));
+                marks.push(Mark::Area {
+                    points: vec![
+                        Point::new(from, 0.0),
+                        Point::new(to, 0.0),
+                        Point::new(to, size.height),
+                        Point::new(from, size.height),
+                    ],
+                    color: gray.scale_alpha(if i % 2 == 0 { 0.08 } else { 0.16 }),
+                });
+                marks.push(Mark::Label {
+                    position: Point::new(from + 4.0, size.height - 18.0),
+                    content: name.to_string(),
+                    color: gray,
+                    size: 12.0,
+                });
+            }
+        }
+
+        if let (Some(envelope), Some(bean)) = (&self.envelope, self.bean()) {
+            let upper = envelope.bands.iter().map(|band| {
+                Point::new(
+                    self.x(band.time, size),
+                    self.y(band.mean + band.stddev, size),
+                )
+            });
+            let lower = envelope.bands.iter().rev().map(|band| {
+                Point::new(
+                    self.x(band.time, size),
+                    self.y(band.mean - band.stddev, size),
+                )
+            });
+
+            marks.push(Mark::Area {
+                points: upper.chain(lower).collect(),
+                color: bean.color.scale_alpha(0.2),
+            });
+        }
+
+        for curve in &self.curves {
+            marks.push(Mark::Line {
+                points: curve.points(self.start_time, self.end_time(), &self.settings, size),
+                color: color.unwrap_or(curve.color),
+                width: 2.5,
+            });
+        }
+
+        if let (true, Some(bean)) = (ror, self.bean()) {
+            marks.push(Mark::Line {
+                points: bean.rate_of_rise().points(
+                    self.start_time,
+                    self.end_time(),
+                    &self.settings,
+                    size,
+                ),
+                color: color.unwrap_or(bean.color).scale_alpha(0.6),
+                width: 1.5,
+            });
+        }
+
+        for (kind, time) in &self.events {
+            let x = self.x(time.duration_since(self.start_time).as_secs_f32(), size);
+            let color = color.unwrap_or(gray);
+
+            marks.push(Mark::Line {
+                points: vec![Point::new(x, 0.0), Point::new(x, size.height)],
+                color: color.scale_alpha(0.5),
+                width: 1.0,
+            });
+            marks.push(Mark::Label {
+                position: Point::new(x + 4.0, 4.0),
+                content: kind.to_string(),
+                color,
+                size: 12.0,
+            });
+        }
+
+        marks
+    }
+
+    /// Border, minute and 50 °C grid lines with their labels.
+    pub fn axes(&self, size: Size, text: Color) -> Vec<Mark> {
+        let mut marks = vec![Mark::Line {
+            points: vec![
+                Point::ORIGIN,
+                Point::new(size.width, 0.0),
+                Point::new(size.width, size.height),
+                Point::new(0.0, size.height),
+                Point::ORIGIN,
+            ],
+            color: text,
+            width: 1.0,
+        }];
+
+        let (_, end) = self.time_window();
+        let step = if end > 20.0 * 60.0 { 2 } else { 1 };
+        for minute in (step..=(end / 60.0) as u32).step_by(step as usize) {
+            let x = self.x(minute as f32 * 60.0, size);
+            marks.push(Mark::Line {
+                points: vec![Point::new(x, size.height - 6.0), Point::new(x, size.height)],
+                color: text,
+                width: 1.0,
+            });
+            marks.push(Mark::Label {
+                position: Point::new(x + 2.0, size.height - 20.0),
+                content: format!("{}'", minute),
+                color: text,
+                size: 11.0,
+            });
+        }
+
+        let (min, max) = self.temp_window();
+        for temp in ((min / 50.0).ceil() as i32..=(max / 50.0) as i32).map(|i| i as f32 * 50.0) {
+            let y = self.y(temp, size);
+            marks.push(Mark::Line {
+                points: vec![Point::new(0.0, y), Point::new(size.width, y)],
+                color: text.scale_alpha(0.15),
+                width: 1.0,
+            });
+            marks.push(Mark::Label {
+                position: Point::new(4.0, y + 2.0),
+                content: format!("{} °C", temp),
+                color: text,
+                size: 11.0,
+            });
+        }
+
+        marks
+    }
+
+    pub fn legend(&self, size: Size, text: Color) -> Vec<Mark> {
+        self.curves
+            .iter()
+            .enumerate()
+            .flat_map(|(i, curve)| {
+                let position = Point::new(size.width - 140.0, 24.0 + i as f32 * 18.0);
+                [
+                    Mark::Line {
+                        points: vec![
+                            Point::new(position.x, position.y + 7.0),
+                            Point::new(position.x + 20.0, position.y + 7.0),
+                        ],
+                        color: curve.color,
+                        width: 2.5,
+                    },
+                    Mark::Label {
+                        position: Point::new(position.x + 26.0, position.y),
+                        content: curve.name.clone(),
+                        color: text,
+                        size: 12.0,
+                    },
+                ]
+            })
+            .collect()
+    }
+}
+
+pub fn draw(frame: &mut Frame, marks: &[Mark]) {
+    for mark in marks {
+        match mark {
+            Mark::Line {
+                points,
+                color,
+                width,
+            } => frame.stroke(
+                &polyline(points),
+                Stroke {
+                    style: canvas::Style::Solid(*color),
+                    width: *width,
+                    ..Default::default()
+                },
+            ),
+            Mark::Area { points, color } => {
+                let area = Path::new(|p| {
+                    if let Some((first, rest)) = points.split_first() {
+                        p.move_to(*first);
+                        for point in rest {
+                            p.line_to(*point);
+                        }
+                        p.close();
+                    }
+                });
+                frame.fill(&area, *color);
+            }
+            Mark::Label {
+                position,
+                content,
+                color,
+                size,
+            } => frame.fill_text(canvas::Text {
+                content: content.clone(),
+                position: *position,
+                color: *color,
+                size: (*size).into(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+fn polyline(points: &[Point]) -> Path {
+    Path::new(|p| {
+        if let Some((first, rest)) = points.split_first() {
+            p.move_to(*first);
+            for point in rest {
+                p.line_to(*point);
+            }
+        }
+    })
+}
+
+/// Several roasts drawn on the same axes, each in a single color.
+#[derive(Debug, Default)]
+pub struct Overlay {
+    pub roasts: Vec<(Roast, Color)>,
+    pub ror: bool,
+}
+
+impl<Message> Program<Message> for Overlay {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let size = bounds.size();
+        let mut frame = Frame::new(renderer, size);
+
+        for (roast, color) in &self.roasts {
+            draw(&mut frame, &roast.marks(size, Some(*color), self.ror));
+        }
+        if let Some((roast, _)) = self.roasts.first() {
+            draw(&mut frame, &roast.axes(size, theme.palette().text));
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+impl<Message> Program<Message> for Roast {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let size = bounds.size();
+        let text = theme.palette().text;
+
+        let mut frame = Frame::new(renderer, size);
+
+        draw(&mut frame, &self.marks(size, None, false));
+        draw(&mut frame, &self.axes(size, text));
+        draw(&mut frame, &self.legend(size, text));
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// The points before and since `at`.
+fn split(points: &[TempData], at: Instant) -> (&[TempData], &[TempData]) {
+    points.split_at(points.partition_point(|temp_data| temp_data.time < at))
+}
+
+fn between(points: &[TempData], start_time: Instant, end_time: Instant) -> &[TempData] {
+    let points = split(points, start_time).1;
+    &points[..points.partition_point(|temp_data| temp_data.time <= end_time)]
+}
+
+/// Seconds since `start_time`, negative before it.
+fn seconds(time: Instant, start_time: Instant) -> f32 {
+    match time.checked_duration_since(start_time) {
+        Some(since) => since.as_secs_f32(),
+        None => -start_time.duration_since(time).as_secs_f32(),
+    }
+}
+
+fn raw_points(points: &[TempData], start_time: Instant) -> Vec<(f32, f32)> {
+    points
+        .iter()
+        .map(|temp_data| (temp_data.temp as f32, seconds(temp_data.time, start_time)))
+        .collect()
+}
+
+fn raw_curve(
+    curve: &RoastCurve,
+    points: &[TempData],
+    raw: &[TempData],
+    start_time: Instant,
+) -> RawCurveData {
+    RawCurveData {
+        id: curve.source_id,
+        name: curve.name.clone(),
+        color: [curve.color.r, curve.color.g, curve.color.b],
+        points: raw_points(points, start_time),
+        raw: raw_points(raw, start_time),
+        filter: curve.filter.settings().clone(),
+        health: curve.health.report(),
+    }
+}
+
+impl From<&Roast> for RawRoastData {
+    fn from(item: &Roast) -> Self {
+        Self {
+            start: item.batch.clone(),
+            end: item.end.clone(),
+            weight_loss: item
+                .end
+                .as_ref()
+                .map(|end| batch::weight_loss(&item.batch, end)),
+            events: item.saved_events(),
+            data: item
+                .curves
+                .iter()
+                .map(|c| {
+                    let points = c.between(item.start_time, item.end_time());
+                    let raw = between(&c.raw, item.start_time, item.end_time());
+                    raw_curve(c, points, raw, item.start_time)
+                })
+                .collect(),
+            preheat: item
+                .curves
+                .iter()
+                .map(|c| {
+                    let points = split(&c.points, item.start_time).0;
+                    let raw = split(&c.raw, item.start_time).0;
+                    raw_curve(c, points, raw, item.start_time)
+                })
+                .filter(|c| !c.raw.is_empty())
+                .collect(),
+        }
+    }
+}
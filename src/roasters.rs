@@ -0,0 +1,350 @@
+use iced::{
+    Element,
+    Length::Fill,
+    Subscription, Task,
+    widget::{button, column, container, row, text},
+};
+
+use crate::{
+    archive::RawRoastData,
+    filter,
+    inventory::Lot,
+    mqtt::Publication,
+    production::PlannedBatch,
+    roasting::{self, Roasting},
+    sensor, server,
+};
+
+/// The roasters of the shop, each with its own sensors and roast.
+#[derive(Debug)]
+pub struct Roasters {
+    machines: Vec<Machine>,
+    last_id: usize,
+    /// Machine and sensor ids of the configured sensors, in the
+    /// configuration order.
+    sensors: Vec<(usize, usize)>,
+    /// The overview is shown when no machine is selected.
+    selected: Option<usize>,
+    /// Large figures of the active machine instead of its screen.
+    display: bool,
+}
+
+#[derive(Debug)]
+struct Machine {
+    id: usize,
+    name: String,
+    roasting: Roasting,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Selected(Option<usize>),
+    Roaster(usize, roasting::Message),
+    ToggleDisplay,
+}
+
+impl Roasters {
+    pub fn boot(machines: &[String], sensors: &[sensor::Config]) -> (Self, Task<Message>) {
+        let mut roasters = Roasters {
+            machines: Vec::new(),
+            last_id: 0,
+            sensors: Vec::new(),
+            selected: None,
+            display: false,
+        };
+        for name in machines {
+            roasters.add_machine(name);
+        }
+        let tasks: Vec<_> = sensors
+            .iter()
+            .map(|config| roasters.add_sensor(config))
+            .collect();
+
+        // A single roaster doesn't need an overview.
+        if let [machine] = roasters.machines.as_slice() {
+            roasters.selected = Some(machine.id);
+        }
+
+        (roasters, Task::batch(tasks))
+    }
+
+    fn machine(&self, id: usize) -> Option<&Machine> {
+        self.machines.iter().find(|machine| machine.id == id)
+    }
+
+    fn machine_mut(&mut self, id: usize) -> Option<&mut Machine> {
+        self.machines.iter_mut().find(|machine| machine.id == id)
+    }
+
+    pub fn add_machine(&mut self, name: &str) {
+        self.machines.push(Machine {
+            id: self.last_id,
+            name: name.to_string(),
+            roasting: Roasting::new(name),
+        });
+        self.last_id += 1;
+    }
+
+    /// Removes a machine without sensors nor roast in progress, returning
+    /// whether it is gone.
+    pub fn remove_machine(&mut self, name: &str) -> bool {
+        let Some(index) = self
+            .machines
+            .iter()
+            .position(|machine| machine.name == name)
+        else {
+            return true;
+        };
+        let id = self.machines[index].id;
+        if self.machines[index].roasting.is_busy()
+            || self.sensors.iter().any(|(machine, _)| *machine == id)
+        {
+            return false;
+        }
+
+        self.machines.remove(index);
+        if self.selected == Some(id) {
+            self.selected = None;
+        }
+        true
+    }
+
+    /// Connects a sensor on its machine, the first one when its machine is
+    /// unknown, returning the machine and sensor ids.
+    fn connect(&mut self, config: &sensor::Config) -> Option<(usize, usize, Task<Message>)> {
+        let index = self
+            .machines
+            .iter()
+            .position(|machine| machine.name == config.machine)
+            .unwrap_or(0);
+        let machine = self.machines.get_mut(index)?;
+        let id = machine.id;
+        let (sensor, task) = machine.roasting.add_sensor(config);
+
+        Some((
+            id,
+            sensor,
+            task.map(move |message| Message::Roaster(id, message)),
+        ))
+    }
+
+    pub fn add_sensor(&mut self, config: &sensor::Config) -> Task<Message> {
+        match self.connect(config) {
+            Some((machine, sensor, task)) => {
+                self.sensors.push((machine, sensor));
+                task
+            }
+            None => Task::none(),
+        }
+    }
+
+    /// Removes the sensor at `index` in the configuration order.
+    pub fn remove_sensor(&mut self, index: usize) {
+        if index < self.sensors.len() {
+            let (machine, sensor) = self.sensors.remove(index);
+            if let Some(machine) = self.machine_mut(machine) {
+                machine.roasting.remove_sensor(sensor);
+            }
+        }
+    }
+
+    /// Moves the sensor at `index` to the machine of its new configuration.
+    pub fn move_sensor(&mut self, index: usize, config: &sensor::Config) -> Task<Message> {
+        let Some(&(machine, sensor)) = self.sensors.get(index) else {
+            return Task::none();
+        };
+        if let Some(machine) = self.machine_mut(machine) {
+            machine.roasting.remove_sensor(sensor);
+        }
+
+        match self.connect(config) {
+            Some((machine, sensor, task)) => {
+                self.sensors[index] = (machine, sensor);
+                task
+            }
+            None => {
+                self.sensors.remove(index);
+                Task::none()
+            }
+        }
+    }
+
+    pub fn set_filter(&mut self, index: usize, settings: filter::Settings) {
+        if let Some(&(machine, sensor)) = self.sensors.get(index) {
+            if let Some(machine) = self.machine_mut(machine) {
+                machine.roasting.set_filter(sensor, settings);
+            }
+        }
+    }
+
+    /// Fills the batch of the selected machine, or of the first idle one.
+    pub fn preload(&mut self, batch: &PlannedBatch) {
+        let id = self
+            .selected
+            .or_else(|| {
+                self.machines
+                    .iter()
+                    .find(|machine| !machine.roasting.is_busy())
+                    .map(|machine| machine.id)
+            })
+            .or(self.machines.first().map(|machine| machine.id));
+
+        if let Some(machine) = id.and_then(|id| self.machine_mut(id)) {
+            machine.roasting.preload(batch);
+            self.selected = id;
+        }
+    }
+
+    /// The machine followed by the live API: the selected one, or the first
+    /// one roasting.
+    fn active(&self) -> Option<&Roasting> {
+        self.active_machine().map(|machine| &machine.roasting)
+    }
+
+    fn active_machine(&self) -> Option<&Machine> {
+        self.selected
+            .and_then(|id| self.machine(id))
+            .or_else(|| {
+                self.machines
+                    .iter()
+                    .find(|machine| machine.roasting.is_busy())
+            })
+            .or(self.machines.first())
+    }
+
+    pub fn is_display(&self) -> bool {
+        self.display
+    }
+
+    pub fn live(&self) -> server::Live {
+        self.active().map(Roasting::live).unwrap_or_default()
+    }
+
+    pub fn roast_data(&self) -> Option<RawRoastData> {
+        self.active().and_then(Roasting::roast_data)
+    }
+
+    pub fn publication(&self, message: &Message) -> Option<Publication> {
+        let Message::Roaster(id, message) = message else {
+            return None;
+        };
+        let machine = self.machine(*id)?;
+
+        machine
+            .roasting
+            .publication(message)
+            .map(|publication| publication.on(&machine.name))
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Selected(id) => {
+                self.selected = id;
+                Task::none()
+            }
+            Message::ToggleDisplay => {
+                self.display = !self.display;
+                Task::none()
+            }
+            Message::Roaster(id, message) => match self.machine_mut(id) {
+                Some(machine) => machine
+                    .roasting
+                    .update(message)
+                    .map(move |message| Message::Roaster(id, message)),
+                None => Task::none(),
+            },
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(self.machines.iter().map(|machine| {
+            machine
+                .roasting
+                .subscription()
+                .with(machine.id)
+                .map(|(id, message)| Message::Roaster(id, message))
+        }))
+    }
+
+    fn overview(&self) -> Element<Message> {
+        let cards = self.machines.iter().map(|machine| {
+            let glance = machine.roasting.glance();
+            let bean = match glance.bean {
+                Some(temp) if glance.connected => text(format!("{:.1} °C", temp)).size(40),
+                _ => text("–").size(40).style(text::secondary),
+            };
+
+            button(
+                column![
+                    text(&machine.name).size(24),
+                    bean,
+                    text(glance.rate_of_rise.map_or("RoR –".to_string(), |ror| {
+                        format!("RoR {:.1} °C/min", ror)
+                    })),
+                    text(glance.state),
+                ]
+                .push_maybe(
+                    glance
+                        .recipe
+                        .map(|recipe| text(recipe).style(text::secondary)),
+                )
+                .spacing(10)
+                .padding(10)
+                .width(250),
+            )
+            .on_press(Message::Selected(Some(machine.id)))
+            .style(button::secondary)
+            .into()
+        });
+
+        container(column![text("Roasters").size(30), row(cards).spacing(20).wrap()].spacing(20))
+            .center_x(Fill)
+            .padding(20)
+            .into()
+    }
+
+    pub fn view<'a>(&'a self, lots: &'a [Lot]) -> Element<'a, Message> {
+        if let (true, Some(machine)) = (self.display, self.active_machine()) {
+            let id = machine.id;
+            return machine
+                .roasting
+                .display()
+                .map(move |message| Message::Roaster(id, message));
+        }
+
+        let content = match self.selected.and_then(|id| self.machine(id)) {
+            Some(machine) => {
+                let id = machine.id;
+                machine
+                    .roasting
+                    .view(lots)
+                    .map(move |message| Message::Roaster(id, message))
+            }
+            None => self.overview(),
+        };
+
+        if self.machines.len() < 2 {
+            return content;
+        }
+
+        let tab = |label: &'a str, target: Option<usize>| -> Element<'a, Message> {
+            button(text(label))
+                .on_press(Message::Selected(target))
+                .style(if self.selected == target {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .into()
+        };
+        let tabs = row![tab("Overview", None)]
+            .extend(
+                self.machines
+                    .iter()
+                    .map(|machine| tab(&machine.name, Some(machine.id))),
+            )
+            .spacing(10);
+
+        column![container(tabs).center_x(Fill).padding([10, 20]), content].into()
+    }
+}
@@ -1,177 +1,824 @@
 use iced::{
     Alignment, Color, Element,
     Length::Fill,
-    Point, Rectangle, Renderer, Size, Subscription, Task, Theme, mouse,
+    Subscription, Task, task,
     time::{self, milliseconds},
-    widget::{
-        button, canvas,
-        canvas::{Frame, Geometry, Path, Program, Stroke},
-        column, container, horizontal_space, row, text,
-    },
+    widget::{button, canvas, column, container, horizontal_space, row, text},
+};
+use std::{
+    io,
+    time::{Duration, Instant},
 };
-use serde::Serialize;
-use std::{fs, time::Instant};
 
-use crate::{preferences::PROJECT_DIRS, sensor};
+use crate::{
+    archive::{self, EventKind, Metrics, RawRoastData},
+    batch, data, detect,
+    filter::{self, Filter},
+    format::{duration, point},
+    health::{self, Health},
+    inventory::Lot,
+    journal::Journal,
+    mqtt::Publication,
+    production::PlannedBatch,
+    reconnect::Backoff,
+    roast::{CurveSettings, Roast, RoastCurve},
+    sensor, server,
+    stats::Envelope,
+};
 use sensor::{Error, TempData};
 
-#[derive(Clone, Debug)]
+/// How long a stopped roast can still be resumed.
+const GRACE: Duration = Duration::from_secs(60);
+
+/// Steps, in seconds, to move the detected charge and drop by.
+const NUDGES: [i32; 4] = [-5, -1, 1, 5];
+
+fn shift(at: Instant, seconds: i32) -> Instant {
+    let by = Duration::from_secs(seconds.unsigned_abs() as u64);
+    if seconds < 0 {
+        at.checked_sub(by).unwrap_or(at)
+    } else {
+        at + by
+    }
+}
+
+#[derive(Debug)]
 pub struct Roasting {
+    name: String,
     sensors: Vec<TempSensor>,
     last_id: usize,
     roast: Option<Roast>,
     roasting: bool,
+    /// When the roast was stopped, unless it was recovered.
+    stopped_at: Option<Instant>,
+    /// When the roast was last resumed, the drops detected before were
+    /// false alarms.
+    resumed_at: Option<Instant>,
+    /// Key figures of the stopped roast.
+    summary: Option<Metrics>,
+    confirm_discard: bool,
+    now: Instant,
+    form: batch::Form,
+    recipes: Vec<String>,
+    /// On-disk copy of the roast in progress.
+    journal: Option<Journal>,
+    status: Option<Result<String, String>>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     SensorUpdated(usize, Update),
     TryReconnect(Instant),
+    Retry(usize),
+    Close(usize),
+    Tick(Instant),
+    Batch(batch::Message),
     StartRoast,
+    /// The spread of earlier roasts of the recipe, read once the roast
+    /// started.
+    EnvelopeLoaded(String, Option<Envelope>),
+    /// Records the curves before charge, which is detected from the bean
+    /// probe or marked by hand.
+    PreHeat,
+    Charge,
+    Mark(EventKind),
     StopRoast,
+    ResumeRoast,
+    /// Stops the roast and asks to discard it.
+    AbortRoast,
+    DiscardRoast,
+    ConfirmDiscard,
+    CancelDiscard,
+    /// Moves the charge or the drop by some seconds, once stopped.
+    MoveCharge(i32),
+    MoveDrop(i32),
+    SaveRoast,
+    Saved(batch::Start),
+    /// The charge or the drop was detected from the bean probe.
+    Charged,
+    Dropped,
 }
 
 impl Roasting {
-    pub fn new_sensor(
+    fn new_sensor(
         &mut self,
-        name: &str,
-        channel: i32,
-        color: Color,
+        config: &sensor::Config,
         curve_settings: CurveSettings,
-    ) -> Task<Update> {
+    ) -> Task<Message> {
         let id = self.last_id;
         self.last_id += 1;
-        self.sensors.push(TempSensor::new(
-            id,
-            name,
-            color,
-            curve_settings,
-            0,
-            572104,
-            channel,
-        ));
-        self.sensors[id].connect()
+
+        let mut sensor = TempSensor::new(id, config, curve_settings);
+        let task = sensor
+            .connect()
+            .map(move |update| Message::SensorUpdated(id, update));
+        self.sensors.push(sensor);
+
+        task
+    }
+
+    fn sensor_mut(&mut self, id: usize) -> Option<&mut TempSensor> {
+        self.sensors.iter_mut().find(|s| s.id == id)
+    }
+
+    /// Connects a sensor, returning its id.
+    pub fn add_sensor(&mut self, config: &sensor::Config) -> (usize, Task<Message>) {
+        let id = self.last_id;
+        (id, self.new_sensor(config, CurveSettings::temperature()))
+    }
+
+    /// Removes a sensor, dropping its connection.
+    pub fn remove_sensor(&mut self, id: usize) {
+        self.sensors.retain(|s| s.id != id);
+    }
+
+    pub fn preload(&mut self, batch: &PlannedBatch) {
+        self.form.preload(batch);
+    }
+
+    pub fn set_filter(&mut self, id: usize, settings: filter::Settings) {
+        if let Some(sensor) = self.sensor_mut(id) {
+            sensor.filter.set_settings(settings);
+        }
+    }
+
+    pub fn live(&self) -> server::Live {
+        server::Live {
+            sensors: self
+                .sensors
+                .iter()
+                .map(|s| server::Reading {
+                    name: s.name.clone(),
+                    temp: s.filtered.as_ref().map(|temp_data| temp_data.temp),
+                    connected: matches!(s.state, State::Connected(_)),
+                })
+                .collect(),
+            roast: self.roast.as_ref().map(|roast| server::Status {
+                recipe: roast.batch.recipe.clone(),
+                recording: self.roasting,
+                preheating: !roast.charged,
+                elapsed: roast.elapsed().as_secs_f32(),
+                rate_of_rise: roast.bean().and_then(|bean| bean.last_rate_of_rise()),
+                next_step: roast.next_step().map(|step| step.to_string()),
+                events: roast.saved_events(),
+            }),
+        }
+    }
+
+    pub fn roast_data(&self) -> Option<RawRoastData> {
+        self.roast.as_ref().map(RawRoastData::from)
+    }
+
+    /// What to publish over MQTT once `message` has been handled.
+    pub fn publication(&self, message: &Message) -> Option<Publication> {
+        match message {
+            Message::SensorUpdated(id, update) => {
+                let sensor = self.sensors.iter().find(|s| s.id == *id)?;
+                match update {
+                    Update::EventReceived(event) => Some(Publication::sensor(&sensor.name, event)),
+                    Update::Disconnected(_) => Some(Publication::connection(&sensor.name, false)),
+                }
+            }
+            Message::StartRoast | Message::PreHeat if self.roasting => self
+                .roast
+                .as_ref()
+                .map(|roast| Publication::started(&roast.batch)),
+            Message::Mark(kind) if self.roasting => self
+                .roast
+                .as_ref()
+                .and_then(|roast| roast.event(*kind))
+                .map(|elapsed| Publication::marked(*kind, elapsed)),
+            Message::Charge | Message::Charged => self
+                .roast
+                .as_ref()
+                .filter(|roast| roast.charged)
+                .map(|_| Publication::charged()),
+            Message::StopRoast | Message::AbortRoast | Message::Dropped => self
+                .roast
+                .as_ref()
+                .map(|roast| Publication::stopped(roast.elapsed().as_secs_f32())),
+            Message::ResumeRoast if self.roasting => self
+                .roast
+                .as_ref()
+                .map(|roast| Publication::resumed(roast.elapsed().as_secs_f32())),
+            Message::ConfirmDiscard if self.roast.is_none() => Some(Publication::discarded()),
+            _ => None,
+        }
+    }
+
+    /// What the overview of the machines shows.
+    pub fn glance(&self) -> Glance {
+        let bean = self.sensors.first();
+
+        Glance {
+            bean: bean
+                .and_then(|s| s.filtered.as_ref())
+                .map(|temp_data| temp_data.temp),
+            connected: bean.is_some_and(|s| matches!(s.state, State::Connected(_))),
+            rate_of_rise: self
+                .roast
+                .as_ref()
+                .and_then(|roast| roast.bean())
+                .and_then(|bean| bean.last_rate_of_rise()),
+            recipe: self
+                .roast
+                .as_ref()
+                .and_then(|roast| roast.batch.recipe.clone()),
+            state: match &self.roast {
+                Some(roast) if self.roasting && !roast.charged => {
+                    format!("Pre-heating {}", duration(roast.elapsed().as_secs_f32()))
+                }
+                Some(roast) if self.roasting => {
+                    format!("Roasting {}", duration(roast.elapsed().as_secs_f32()))
+                }
+                Some(_) => "Waiting to be saved".to_string(),
+                None => "Idle".to_string(),
+            },
+        }
     }
 
-    pub fn boot() -> (Self, Task<Message>) {
+    /// Creates a roaster, recovering the roast it was recording if the
+    /// application was interrupted.
+    pub fn new(name: &str) -> Self {
         let mut roasting = Self {
+            name: name.to_string(),
             sensors: Vec::new(),
             last_id: 0,
             roast: None,
             roasting: false,
+            stopped_at: None,
+            resumed_at: None,
+            summary: None,
+            confirm_discard: false,
+            now: Instant::now(),
+            form: batch::Form::for_machine(name),
+            recipes: data::recipes()
+                .iter()
+                .map(|recipe| recipe.name().clone())
+                .collect(),
+            journal: None,
+            status: None,
         };
 
-        let bean_task = roasting.new_sensor(
-            "Bean",
-            0,
-            Color::from_rgb(0., 0.5, 1.),
-            CurveSettings {
-                min: 0.0,
-                max: 230.0,
-                fit: CurveFit::Normal,
-            },
-        );
-        let exhaust_task = roasting.new_sensor(
-            "Exhaust",
-            1,
-            Color::from_rgb(1., 0., 0.),
-            CurveSettings {
-                min: 0.0,
-                max: 230.0,
-                fit: CurveFit::Normal,
-            },
-        );
-
-        (
-            roasting,
-            Task::batch([
-                bean_task.map(|update| Message::SensorUpdated(0, update)),
-                exhaust_task.map(|update| Message::SensorUpdated(1, update)),
-            ]),
-        )
+        match Journal::recover(name) {
+            Ok(Some((roast, journal))) => {
+                roasting.summary = Some(RawRoastData::from(&roast).metrics());
+                roasting.roast = Some(roast);
+                roasting.journal = Some(journal);
+                roasting.status = Some(Ok(
+                    "An interrupted roast was recovered, save it to keep it".to_string(),
+                ));
+            }
+            Ok(None) => {}
+            Err(error) => {
+                roasting.status = Some(Err(format!(
+                    "The interrupted roast could not be recovered: {}",
+                    error
+                )));
+            }
+        }
+
+        roasting
+    }
+
+    /// Whether the roast was stopped within the grace period.
+    fn can_resume(&self) -> bool {
+        self.roast.is_some()
+            && self
+                .stopped_at
+                .is_some_and(|stopped_at| self.now.duration_since(stopped_at) < GRACE)
+    }
+
+    /// Stops journaling after a failed write, the roast stays in memory.
+    fn journal_failed(&mut self, error: io::Error) {
+        self.journal = None;
+        self.status = Some(Err(format!(
+            "The roast is no longer written to disk as it goes: {}",
+            error
+        )));
+    }
+
+    /// Removes the journal of a roast that is saved or discarded. One left
+    /// behind would be recovered as a second copy of the roast.
+    fn remove_journal(&mut self) {
+        if let Some(Err(error)) = self.journal.take().map(Journal::remove) {
+            self.status = Some(Err(format!(
+                "The journal of the roast could not be removed, delete it before the next launch: {}",
+                error
+            )));
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.roast.is_some()
+    }
+
+    /// Starts recording a roast, at once or from the pre-heat, and loads the
+    /// spread of earlier roasts of its recipe.
+    fn start(&mut self, charged: bool) -> Task<Message> {
+        let Some(batch) = self.form.start() else {
+            return Task::none();
+        };
+        let curves = self
+            .sensors
+            .iter()
+            .map(|s| {
+                RoastCurve::new(
+                    s.id,
+                    &s.name,
+                    s.color,
+                    s.curve_settings.clone(),
+                    Filter::new(s.filter.settings().clone()),
+                )
+            })
+            .collect();
+        let envelope = match batch.recipe.clone() {
+            Some(recipe) => Task::perform(Envelope::for_recipe(recipe.clone()), |envelope| {
+                Message::EnvelopeLoaded(recipe, envelope)
+            }),
+            None => Task::none(),
+        };
+        let roast = Roast {
+            charged,
+            ..Roast::new(curves, CurveSettings::time(), batch)
+        };
+        self.roasting = true;
+        self.resumed_at = None;
+
+        self.status = None;
+        match Journal::create(&self.name, &roast) {
+            Ok(journal) => self.journal = Some(journal),
+            Err(error) => self.journal_failed(error),
+        }
+        self.roast = Some(roast);
+        envelope
+    }
+
+    /// Stops recording at the drop, at the last reading unless detected.
+    fn stop(&mut self) {
+        if !self.roasting {
+            return;
+        }
+        self.roasting = false;
+        // `now` lags behind when no sensor is connected.
+        self.now = Instant::now();
+        self.stopped_at = Some(self.now);
+
+        let Some(roast) = &mut self.roast else {
+            return;
+        };
+        let at = roast.end_time();
+        roast.drop_time = Some(at);
+        self.summary = Some(RawRoastData::from(&*roast).metrics());
+        if let Some(Err(error)) = self
+            .journal
+            .as_mut()
+            .map(|journal| journal.drop_time(Some(at)))
+        {
+            self.journal_failed(error);
+        }
+    }
+
+    /// Records the charge and drop after they were moved, and sums the roast
+    /// up again.
+    fn realign(&mut self) {
+        let Some(roast) = &self.roast else {
+            return;
+        };
+        self.summary = Some(RawRoastData::from(roast).metrics());
+
+        let (charge, drop) = (roast.start_time, roast.drop_time);
+        if let Some(Err(error)) = self
+            .journal
+            .as_mut()
+            .map(|journal| journal.charge(charge).and_then(|_| journal.drop_time(drop)))
+        {
+            self.journal_failed(error);
+        }
+    }
+
+    fn charge(&mut self, at: Instant) {
+        let Some(roast) = &mut self.roast else {
+            return;
+        };
+        roast.charge(at);
+        if let Some(Err(error)) = self.journal.as_mut().map(|journal| journal.charge(at)) {
+            self.journal_failed(error);
+        }
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::SensorUpdated(id, update) => {
-                let _ = self.sensors[id].update(update);
-                if let State::Connected(temp_data) = &self.sensors[id].state {
-                    if let Some(roast) = &mut self.roast {
-                        roast.curves[id].points.push(temp_data.clone());
+                let Some(sensor) = self.sensors.iter_mut().find(|s| s.id == id) else {
+                    return Task::none();
+                };
+                let _ = sensor.update(update);
+                let mut failed = None;
+                let mut charge = None;
+                let mut drop = None;
+                if let State::Connected(temp_data) = &sensor.state {
+                    self.now = temp_data.time;
+                    if let (true, Some(roast)) = (self.roasting, &mut self.roast) {
+                        if let Some(index) = roast.curves.iter().position(|c| c.source_id == id) {
+                            roast.curves[index].push(temp_data);
+                            if let Some(journal) = &mut self.journal {
+                                failed = journal.sample(index, temp_data).err();
+                            }
+                        }
                         roast.last_time = temp_data.time;
+                        if let Some(bean) = roast.bean().filter(|bean| bean.source_id == id) {
+                            if !roast.charged {
+                                charge = detect::fall(&bean.points, roast.start_time);
+                            } else {
+                                drop = detect::drop_time(
+                                    &bean.points,
+                                    roast.start_time,
+                                    self.resumed_at.unwrap_or(roast.start_time),
+                                );
+                            }
+                        }
                     }
                 }
+                if let Some(error) = failed {
+                    self.journal_failed(error);
+                }
+                if let Some(at) = charge {
+                    self.charge(at);
+                    return Task::done(Message::Charged);
+                }
+                if let (Some(at), Some(roast)) = (drop, &mut self.roast) {
+                    roast.drop_time = Some(at);
+                    let elapsed = roast.elapsed().as_secs_f32();
+                    self.stop();
+                    self.status = Some(Ok(format!(
+                        "Drop detected at {}, move it below if needed",
+                        duration(elapsed)
+                    )));
+                    return Task::done(Message::Dropped);
+                }
                 Task::none()
             }
-            Message::TryReconnect(_) => {
-                Task::batch(self.sensors.iter_mut().enumerate().map(|(i, s)| {
+            Message::Tick(now) => {
+                self.now = now;
+                Task::none()
+            }
+            Message::TryReconnect(now) => {
+                self.now = now;
+                Task::batch(self.sensors.iter_mut().map(|s| {
+                    let id = s.id;
                     match s.state {
-                        State::Disconnected | State::Errored(_) => s
+                        State::Disconnected | State::Errored(_) if s.backoff.is_due(now) => s
                             .connect()
-                            .map(move |update| Message::SensorUpdated(i, update)),
+                            .map(move |update| Message::SensorUpdated(id, update)),
                         _ => Task::none(),
                     }
                 }))
             }
-            Message::StartRoast => {
-                self.roast = Some(Roast::new(
-                    &self.sensors,
-                    CurveSettings {
-                        min: 0.0,
-                        max: 17.0 * 60.0,
-                        fit: CurveFit::Padding(0.0, 10.0),
-                    },
-                ));
+            Message::Retry(id) => match self.sensor_mut(id) {
+                Some(sensor) => sensor
+                    .connect()
+                    .map(move |update| Message::SensorUpdated(id, update)),
+                None => Task::none(),
+            },
+            Message::Close(id) => {
+                if let Some(sensor) = self.sensor_mut(id) {
+                    sensor.close();
+                }
+                Task::none()
+            }
+            Message::Batch(message) => {
+                self.form.update(message);
+                Task::none()
+            }
+            Message::StartRoast => self.start(true),
+            Message::PreHeat => self.start(false),
+            Message::EnvelopeLoaded(recipe, envelope) => {
+                if let Some(roast) = self
+                    .roast
+                    .as_mut()
+                    .filter(|roast| roast.batch.recipe.as_ref() == Some(&recipe))
+                {
+                    roast.envelope = envelope;
+                }
+                Task::none()
+            }
+            Message::Charge => {
+                if let Some(at) = self
+                    .roast
+                    .as_ref()
+                    .filter(|roast| !roast.charged)
+                    .map(|roast| roast.last_time)
+                {
+                    self.charge(at);
+                }
                 Task::none()
             }
-            Message::StopRoast => {
-                if let Some(roast) = &self.roast {
-                    let mut path = PROJECT_DIRS.data_dir().join("_").to_path_buf();
-                    let now = chrono::offset::Local::now();
-                    let file_name = format!("roast_{}.json", now.format("%d-%m-%Y_%Hh%M"));
-                    path.set_file_name(file_name);
-                    if let Some(parent) = path.parent() {
-                        fs::create_dir_all(parent).unwrap();
+            Message::Mark(kind) => {
+                if let (true, Some(roast)) = (self.roasting, &mut self.roast) {
+                    if !roast.charged {
+                        return Task::none();
+                    }
+                    roast.mark(kind);
+                    let at = roast.last_time;
+                    if let Some(Err(error)) =
+                        self.journal.as_mut().map(|journal| journal.event(kind, at))
+                    {
+                        self.journal_failed(error);
+                    }
+                }
+                Task::none()
+            }
+            Message::StopRoast | Message::AbortRoast => {
+                self.stop();
+                self.confirm_discard = matches!(message, Message::AbortRoast);
+                Task::none()
+            }
+            Message::ResumeRoast => {
+                if self.can_resume() {
+                    self.roasting = true;
+                    self.stopped_at = None;
+                    self.resumed_at = Some(self.now);
+                    self.summary = None;
+                    self.confirm_discard = false;
+                    if let Some(roast) = &mut self.roast {
+                        roast.drop_time = None;
+                    }
+                    if let Some(Err(error)) =
+                        self.journal.as_mut().map(|journal| journal.drop_time(None))
+                    {
+                        self.journal_failed(error);
                     }
-                    let raw_roast_data: RawRoastData = roast.into();
-                    fs::write(path, serde_json::to_string(&raw_roast_data).unwrap()).unwrap();
                 }
+                Task::none()
+            }
+            Message::DiscardRoast => {
+                self.confirm_discard = true;
+                Task::none()
+            }
+            Message::CancelDiscard => {
+                self.confirm_discard = false;
+                Task::none()
+            }
+            Message::MoveCharge(seconds) => {
+                if let (false, Some(roast)) = (self.roasting, &mut self.roast) {
+                    roast.move_charge(shift(roast.start_time, seconds));
+                    self.realign();
+                }
+                Task::none()
+            }
+            Message::MoveDrop(seconds) => {
+                if let (false, Some(roast)) = (self.roasting, &mut self.roast) {
+                    roast.move_drop(shift(roast.end_time(), seconds));
+                    self.realign();
+                }
+                Task::none()
+            }
+            Message::ConfirmDiscard => {
                 self.roast = None;
+                self.roasting = false;
+                self.stopped_at = None;
+                self.summary = None;
+                self.confirm_discard = false;
+                self.status = Some(Ok("The roast was discarded".to_string()));
+                self.remove_journal();
                 Task::none()
             }
+            Message::SaveRoast => {
+                let Some(end) = self.form.end() else {
+                    return Task::none();
+                };
+
+                let Some(roast) = &mut self.roast else {
+                    return Task::none();
+                };
+                roast.end = Some(end);
+
+                // The roast and its journal are kept to retry on failure.
+                let raw_roast_data: RawRoastData = (&*roast).into();
+                if let Err(error) = archive::save(&raw_roast_data) {
+                    self.status = Some(Err(format!("The roast could not be saved: {}", error)));
+                    return Task::none();
+                }
+
+                self.form.clear_end();
+                self.status = None;
+                self.stopped_at = None;
+                self.summary = None;
+                self.remove_journal();
+                match self.roast.take() {
+                    Some(roast) => Task::done(Message::Saved(roast.batch)),
+                    None => Task::none(),
+                }
+            }
+            Message::Saved(_) | Message::Charged | Message::Dropped => Task::none(),
         }
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(self.sensors.iter().map(|s| s.subscription()))
+        // The clock also runs down the grace period when no sensor is
+        // connected.
+        let tick = if self.can_resume()
+            || self
+                .sensors
+                .iter()
+                .any(|s| matches!(s.state, State::Connected(_)))
+        {
+            time::every(Duration::from_secs(1)).map(Message::Tick)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch(self.sensors.iter().map(|s| s.subscription()).chain([tick]))
     }
 
-    pub fn view(&self) -> Element<Message> {
-        let title = text("Roasting").size(30);
-        let sensors = column(self.sensors.iter().map(|s| s.view()))
+    /// The dialog shown once the roast is stopped.
+    fn view_summary<'a>(&'a self, roast: &'a Roast) -> Element<'a, Message> {
+        let metrics = self.summary.clone().unwrap_or_default();
+
+        let figures = column![
+            text("Roast summary").size(20),
+            text(format!("Total time: {}", duration(metrics.total_time))),
+            text(format!("First crack: {}", point(metrics.first_crack))),
+            text(format!("Drop: {}", point(metrics.drop))),
+            text(format!(
+                "Development: {}",
+                metrics
+                    .development
+                    .map_or("–".to_string(), |dtr| format!("{:.1} %", dtr))
+            )),
+        ]
+        .spacing(5);
+
+        let nudges = |label: String, on_press: fn(i32) -> Message| {
+            row![text(label).width(200)]
+                .extend(NUDGES.iter().map(|seconds| {
+                    button(text(format!("{:+} s", seconds)).size(14))
+                        .on_press(on_press(*seconds))
+                        .style(button::secondary)
+                        .into()
+                }))
+                .spacing(10)
+                .align_y(Alignment::Center)
+        };
+        let timeline = column![
+            nudges(
+                format!(
+                    "Charge: {} into the recording",
+                    duration(
+                        roast
+                            .start_time
+                            .duration_since(roast.first_time())
+                            .as_secs_f32()
+                    )
+                ),
+                Message::MoveCharge,
+            ),
+            nudges(
+                format!("Drop: {}", duration(roast.elapsed().as_secs_f32())),
+                Message::MoveDrop,
+            ),
+        ]
+        .spacing(5);
+
+        let actions: Element<_> = if self.confirm_discard {
+            row![
+                text("Discard this roast? It can't be recovered."),
+                button("Discard")
+                    .on_press(Message::ConfirmDiscard)
+                    .style(button::danger),
+                button("Keep")
+                    .on_press(Message::CancelDiscard)
+                    .style(button::secondary),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        } else {
+            let resume = self
+                .stopped_at
+                .filter(|_| self.can_resume())
+                .map(|stopped_at| {
+                    let left = GRACE.saturating_sub(self.now.duration_since(stopped_at));
+                    button(text(format!("Continue Roasting ({} s)", left.as_secs())))
+                        .on_press(Message::ResumeRoast)
+                        .style(button::secondary)
+                });
+
+            row![
+                button("Save Roast")
+                    .on_press(Message::SaveRoast)
+                    .style(button::success)
+            ]
+            .push_maybe(resume)
+            .push(
+                button("Discard")
+                    .on_press(Message::DiscardRoast)
+                    .style(button::danger),
+            )
+            .spacing(10)
+            .into()
+        };
+
+        column![
+            figures,
+            timeline,
+            self.form.view_end(&roast.batch).map(Message::Batch),
+            actions
+        ]
+        .spacing(20)
+        .max_width(600)
+        .into()
+    }
+
+    pub fn view<'a>(&'a self, lots: &'a [Lot]) -> Element<'a, Message> {
+        let title = column![text(&self.name).size(30)]
+            .push_maybe(self.status.as_ref().map(|status| match status {
+                Ok(message) => text(message).style(text::success),
+                Err(error) => text(error).style(text::danger),
+            }))
+            .align_x(Alignment::Center)
+            .spacing(10);
+        let sensors = column(self.sensors.iter().map(|s| s.view(self.now)))
             .max_width(800)
             .spacing(20);
 
-        let canvas: Element<_> = if let Some(roast) = &self.roast {
-            column![
+        let canvas: Element<_> = match &self.roast {
+            Some(roast) if self.roasting && !roast.charged => column![
                 canvas(roast).width(Fill).height(Fill),
                 container(
-                    button("Stop Roast")
-                        .on_press(Message::StopRoast)
-                        .style(button::danger),
+                    row![
+                        text(format!(
+                            "Pre-heating for {}, waiting for the charge...",
+                            duration(roast.elapsed().as_secs_f32())
+                        )),
+                        button("Charge")
+                            .on_press(Message::Charge)
+                            .style(button::success),
+                        button("Cancel")
+                            .on_press(Message::ConfirmDiscard)
+                            .style(button::secondary),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center)
                 )
                 .center_x(Fill)
             ]
             .spacing(20)
-            .into()
-        } else {
-            container(
-                button("Start Roast")
-                    .on_press(Message::StartRoast)
-                    .style(button::success),
-            )
-            .center_x(Fill)
-            .into()
+            .into(),
+            Some(roast) if self.roasting => column![
+                canvas(roast).width(Fill).height(Fill),
+                container(
+                    row(EventKind::ALL.iter().map(|kind| {
+                        button(text(kind.to_string()))
+                            .on_press(Message::Mark(*kind))
+                            .style(button::secondary)
+                            .into()
+                    }))
+                    .push(
+                        button("Stop Roast")
+                            .on_press(Message::StopRoast)
+                            .style(button::danger),
+                    )
+                    .push(
+                        button("Abort")
+                            .on_press(Message::AbortRoast)
+                            .style(button::secondary),
+                    )
+                    .spacing(10)
+                )
+                .center_x(Fill)
+            ]
+            .spacing(20)
+            .into(),
+            Some(roast) => column![
+                canvas(roast).width(Fill).height(Fill),
+                container(self.view_summary(roast)).center_x(Fill),
+            ]
+            .spacing(20)
+            .into(),
+            None => column![
+                container(
+                    self.form
+                        .view_start(lots, &self.recipes)
+                        .map(Message::Batch)
+                )
+                .center_x(Fill),
+                container(
+                    row![
+                        button("Pre-heat")
+                            .on_press(Message::PreHeat)
+                            .style(button::secondary),
+                        button("Start Roast")
+                            .on_press(Message::StartRoast)
+                            .style(button::success),
+                    ]
+                    .spacing(10)
+                )
+                .center_x(Fill)
+            ]
+            .spacing(20)
+            .into(),
         };
 
         let roasting = column![
@@ -185,6 +832,63 @@ impl Roasting {
             .padding(20)
             .into()
     }
+
+    /// Large figures for an operator standing a few meters away.
+    pub fn display(&self) -> Element<Message> {
+        let glance = self.glance();
+        let (elapsed, phase, next) = match &self.roast {
+            Some(roast) if !roast.charged => (roast.elapsed(), "Pre-heat", None),
+            Some(roast) if self.roasting => (
+                roast.elapsed(),
+                roast.phase(),
+                roast.next_step().map(|step| step.to_string()),
+            ),
+            Some(roast) => (roast.elapsed(), "Dropped", None),
+            None => (Duration::ZERO, "Idle", None),
+        };
+        let figure = |label: &'static str, value: String| {
+            column![
+                text(label).size(32).style(text::secondary),
+                text(value).size(150)
+            ]
+            .align_x(Alignment::Center)
+        };
+
+        let bean = match glance.bean {
+            Some(temp) if glance.connected => format!("{:.1}°", temp),
+            _ => "–".to_string(),
+        };
+        let rate_of_rise = glance
+            .rate_of_rise
+            .map_or("–".to_string(), |ror| format!("{:.1}", ror));
+
+        let display = column![
+            row![
+                text(&self.name).size(40),
+                horizontal_space(),
+                text(phase).size(60)
+            ]
+            .align_y(Alignment::Center),
+            text(duration(elapsed.as_secs_f32())).size(240),
+            row![figure("Bean °C", bean), figure("RoR °C/min", rate_of_rise)].spacing(120),
+        ]
+        .push_maybe(next.map(|step| text(format!("Next: {}", step)).size(60)))
+        .push(text("F11 to leave").size(14).style(text::secondary))
+        .align_x(Alignment::Center)
+        .spacing(30);
+
+        container(display).center(Fill).padding(40).into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Glance {
+    pub bean: Option<f64>,
+    pub connected: bool,
+    /// Bean rate of rise in °C/min.
+    pub rate_of_rise: Option<f64>,
+    pub recipe: Option<String>,
+    pub state: String,
 }
 
 #[derive(Debug, Clone)]
@@ -197,60 +901,99 @@ pub enum Update {
 enum State {
     #[default]
     Created,
+    Connecting,
     Connected(TempData),
     Disconnected,
     Errored(Error),
+    Closed,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct TempSensor {
     id: usize,
     name: String,
     color: Color,
     curve_settings: CurveSettings,
-    hub_port: i32,
-    serial_number: i32,
-    channel: i32,
+    config: sensor::Config,
     state: State,
+    health: Health,
+    filter: Filter,
+    filtered: Option<TempData>,
+    backoff: Backoff,
+    connection: Option<task::Handle>,
 }
 
 impl TempSensor {
-    fn new(
-        id: usize,
-        name: &str,
-        color: Color,
-        curve_settings: CurveSettings,
-        hub_port: i32,
-        serial_number: i32,
-        channel: i32,
-    ) -> Self {
+    fn new(id: usize, config: &sensor::Config, curve_settings: CurveSettings) -> Self {
+        let [r, g, b] = config.color;
+
         Self {
             id,
-            name: name.to_string(),
-            color,
+            name: config.name.clone(),
+            color: Color::from_rgb(r, g, b),
             curve_settings,
-            hub_port,
-            serial_number,
-            channel,
+            config: config.clone(),
             state: State::default(),
+            health: Health::default(),
+            filter: Filter::new(config.filter.clone()),
+            filtered: None,
+            backoff: Backoff::default(),
+            connection: None,
         }
     }
 
     fn connect(&mut self) -> Task<Update> {
         match self.state {
-            State::Created | State::Disconnected | State::Errored(_) => Task::sip(
-                sensor::connect_temperature(self.hub_port, self.serial_number, self.channel),
-                Update::EventReceived,
-                Update::Disconnected,
-            ),
-            State::Connected(_) => Task::none(),
+            State::Created | State::Disconnected | State::Errored(_) | State::Closed => {
+                if !matches!(self.state, State::Created) {
+                    let attempt = self.backoff.start_attempt();
+                    self.backoff
+                        .log(&self.name, format!("Reconnection attempt {}", attempt));
+                    self.state = State::Connecting;
+                }
+
+                let (task, handle) = Task::sip(
+                    sensor::connect(&self.config),
+                    Update::EventReceived,
+                    Update::Disconnected,
+                )
+                .abortable();
+                self.connection = Some(handle.abort_on_drop());
+
+                task
+            }
+            State::Connecting | State::Connected(_) => Task::none(),
         }
     }
 
+    /// Aborts the connection task, which closes the channel.
+    fn close(&mut self) {
+        self.connection = None;
+        self.backoff.reset();
+        self.backoff.log(&self.name, "Closed".to_string());
+        self.state = State::Closed;
+    }
+
     fn update(&mut self, update: Update) -> Task<Update> {
+        if matches!(self.state, State::Closed) {
+            return Task::none();
+        }
+
         match update {
             Update::EventReceived(event) => match event {
                 sensor::Event::Change(td) => {
+                    self.health.record(&td);
+                    if let Some(filtered) = self.filter.apply(&td) {
+                        self.filtered = Some(filtered);
+                    }
+                    if self.backoff.attempt() > 0 {
+                        let attempts = self.backoff.attempt();
+                        self.backoff.log(
+                            &self.name,
+                            format!("Reconnected after {} attempts", attempts),
+                        );
+                        self.backoff.reset();
+                    }
                     self.state = State::Connected(td);
                     Task::none()
                 }
@@ -260,15 +1003,22 @@ impl TempSensor {
                 }
                 sensor::Event::Detach => {
                     println!("Detach: {}", self.name);
+                    self.backoff.log(&self.name, "Detached".to_string());
+                    self.backoff.schedule(Instant::now());
                     self.state = State::Disconnected;
                     Task::none()
                 }
             },
             Update::Disconnected(result) => {
+                self.connection = None;
                 self.state = match result {
                     Ok(_) => State::Disconnected,
-                    Err(error) => State::Errored(error),
+                    Err(error) => {
+                        self.backoff.log(&self.name, format!("Error: {}", error));
+                        State::Errored(error)
+                    }
                 };
+                self.backoff.schedule(Instant::now());
                 Task::none()
             }
         }
@@ -283,216 +1033,85 @@ impl TempSensor {
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self, now: Instant) -> Element<Message> {
         let temp = match &self.state {
             State::Created => text("Loading...").style(text::base),
-            State::Connected(temp_data) => {
-                text(format!("{:.1} °C", temp_data.temp)).color(self.color)
-            }
+            State::Connecting => text("Reconnecting...").style(text::base),
+            State::Connected(temp_data) => text(format!(
+                "{:.1} °C",
+                self.filtered.as_ref().unwrap_or(temp_data).temp
+            ))
+            .color(self.color),
             State::Disconnected => text("Disconnected!").style(text::danger),
             State::Errored(error) => text(format!("Error! {}", error)).style(text::danger),
+            State::Closed => text("Closed").style(text::secondary),
+        };
+
+        let action = match self.state {
+            State::Closed => button(text("Open").size(14))
+                .on_press(Message::Retry(self.id))
+                .style(button::secondary),
+            _ => button(text("Close").size(14))
+                .on_press(Message::Close(self.id))
+                .style(button::secondary),
         };
 
-        row![
+        let reading = row![
             text(format!("{}:", self.name)).color(self.color),
             horizontal_space(),
-            temp.size(25)
+            temp.size(25),
+            horizontal_space().width(20),
+            action,
         ]
-        .width(200)
-        .align_y(Alignment::Center)
-        .into()
-    }
-}
-
-#[derive(Clone, Debug, Default)]
-enum CurveFit {
-    #[default]
-    Normal,
-    Padding(f32, f32),
-    AlwaysFit(f32, f32),
-}
-
-#[derive(Debug, Clone)]
-struct CurveSettings {
-    min: f32,
-    max: f32,
-    fit: CurveFit,
-}
+        .align_y(Alignment::Center);
 
-impl CurveSettings {
-    fn window(&self, min: f32, max: f32) -> (f32, f32) {
-        match self.fit {
-            CurveFit::Normal => (self.min, self.max),
-            CurveFit::Padding(pl, pr) => (self.min.min(min - pl), self.max.max(max + pr)),
-            CurveFit::AlwaysFit(pl, pr) => (min - pl, max + pr),
-        }
-    }
-
-    fn fit(window: (f32, f32), v: f32, size: f32) -> f32 {
-        (v - window.0) / (window.1 - window.0) * size
-    }
-
-    fn fit_flip(window: (f32, f32), v: f32, size: f32) -> f32 {
-        (1.0 - (v - window.0) / (window.1 - window.0)) * size
-    }
-}
-
-#[derive(Debug, Clone)]
-struct RoastCurve {
-    source_id: usize,
-    color: Color,
-    settings: CurveSettings,
-    points: Vec<TempData>,
-}
-
-impl RoastCurve {
-    fn new(id: usize, color: Color, curve_settings: CurveSettings) -> Self {
-        Self {
-            source_id: id,
-            color: color,
-            settings: curve_settings,
-            points: Vec::new(),
-        }
-    }
-
-    fn path(
-        &self,
-        start_time: Instant,
-        last_time: Instant,
-        t_settings: &CurveSettings,
-        size: Size,
-    ) -> Path {
-        Path::new(|p| {
-            let iter = self.points.iter().map(|p| p.temp as f32);
-            let min = iter.clone().reduce(f32::min).unwrap_or(0.);
-            let max = iter.reduce(f32::max).unwrap_or(0.);
-
-            let mut points = self.points.iter();
-            if let Some(temp_data) = points.next() {
-                let t_window =
-                    t_settings.window(0.0, last_time.duration_since(start_time).as_secs() as f32);
-                let v_window = self.settings.window(min, max);
-
-                p.move_to(Point::new(
-                    CurveSettings::fit(
-                        t_window,
-                        temp_data.time.duration_since(start_time).as_secs() as f32,
-                        size.width,
-                    ),
-                    CurveSettings::fit_flip(v_window, temp_data.temp as f32, size.height),
-                ));
+        let status: Option<Element<Message>> = match (&self.state, self.health.status(now)) {
+            (State::Connected(_), health::Status::Degraded(issue)) => Some(
+                text(format!("Degraded: {}", issue))
+                    .size(14)
+                    .style(text::danger)
+                    .into(),
+            ),
+            (State::Connecting, _) => Some(
+                text(format!("Attempt {}", self.backoff.attempt()))
+                    .size(14)
+                    .into(),
+            ),
+            (State::Disconnected | State::Errored(_), _) => {
+                let next_in = self
+                    .backoff
+                    .next_at()
+                    .map(|next_at| next_at.saturating_duration_since(now).as_secs_f32())
+                    .unwrap_or_default();
+                let last_event = self
+                    .backoff
+                    .history()
+                    .last()
+                    .map(|(time, event)| format!("{} {}", time.format("%H:%M:%S"), event));
 
-                for temp_data in points {
-                    p.line_to(Point::new(
-                        CurveSettings::fit(
-                            t_window,
-                            temp_data.time.duration_since(start_time).as_secs() as f32,
-                            size.width,
-                        ),
-                        CurveSettings::fit_flip(v_window, temp_data.temp as f32, size.height),
-                    ));
-                }
+                Some(
+                    column![
+                        row![
+                            text(format!(
+                                "Reconnecting (attempt {}, next in {:.1}s)",
+                                self.backoff.attempt() + 1,
+                                next_in
+                            ))
+                            .size(14),
+                            horizontal_space(),
+                            button(text("Retry").size(14))
+                                .on_press(Message::Retry(self.id))
+                                .style(button::secondary),
+                        ]
+                        .align_y(Alignment::Center),
+                    ]
+                    .push_maybe(last_event.map(|event| text(event).size(12)))
+                    .into(),
+                )
             }
-        })
-    }
-}
-
-#[derive(Clone, Debug)]
-struct Roast {
-    start_time: Instant,
-    last_time: Instant,
-    curves: Vec<RoastCurve>,
-    settings: CurveSettings,
-}
-
-impl Roast {
-    fn new(sensors: &Vec<TempSensor>, curve_settings: CurveSettings) -> Self {
-        let now = Instant::now();
-
-        Self {
-            start_time: now,
-            last_time: now,
-            curves: sensors
-                .iter()
-                .map(|s| RoastCurve::new(s.id, s.color, s.curve_settings.clone()))
-                .collect(),
-            settings: curve_settings,
-        }
-    }
-}
-
-impl<Message> Program<Message> for Roast {
-    type State = ();
-
-    fn draw(
-        &self,
-        _state: &(),
-        renderer: &Renderer,
-        theme: &Theme,
-        bounds: Rectangle,
-        _cursor: mouse::Cursor,
-    ) -> Vec<Geometry> {
-        let size = bounds.size();
-
-        let mut frame = Frame::new(renderer, size);
-
-        for curve in &self.curves {
-            let path = curve.path(self.start_time, self.last_time, &self.settings, size);
-
-            frame.stroke(
-                &path,
-                Stroke {
-                    style: iced::widget::canvas::Style::Solid(curve.color),
-                    width: 2.5,
-                    ..Default::default()
-                },
-            );
-        }
-
-        frame.stroke(
-            &Path::rectangle(Point::ORIGIN, frame.size()),
-            Stroke {
-                style: iced::widget::canvas::Style::Solid(theme.palette().text),
-                width: 1.0,
-                ..Default::default()
-            },
-        );
-
-        vec![frame.into_geometry()]
-    }
-}
-
-#[derive(Serialize)]
-struct RawCurveData {
-    id: usize,
-    points: Vec<(f32, f32)>,
-}
-
-#[derive(Serialize)]
-struct RawRoastData {
-    data: Vec<RawCurveData>,
-}
+            _ => None,
+        };
 
-impl From<&Roast> for RawRoastData {
-    fn from(item: &Roast) -> Self {
-        Self {
-            data: item
-                .curves
-                .iter()
-                .map(|c| RawCurveData {
-                    id: c.source_id,
-                    points: c
-                        .points
-                        .iter()
-                        .map(|temp_data| {
-                            (
-                                temp_data.temp as f32,
-                                temp_data.time.duration_since(item.start_time).as_secs() as f32,
-                            )
-                        })
-                        .collect(),
-                })
-                .collect(),
-        }
+        column![reading].push_maybe(status).width(350).into()
     }
 }
@@ -1,54 +1,118 @@
-use iced::task::{Straw, sipper};
-
-use std::sync::mpsc;
-use std::time::Instant;
+use iced::{
+    futures::{StreamExt, channel::mpsc},
+    task::{Straw, sipper},
+};
+use serde::{Deserialize, Serialize};
 
-pub use phidget::errors::Error;
+use std::{
+    fmt, io,
+    ops::{Deref, DerefMut},
+    time::Instant,
+};
 
 use phidget::{
-    Phidget, TIMEOUT_DEFAULT,
+    ChannelClass, DeviceClass, GenericPhidget, Manager, Phidget, TIMEOUT_DEFAULT,
     devices::{TemperatureSensor, temperature_sensor::ThermocoupleType},
 };
 
+use crate::{filter, modbus, serial};
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Phidget(phidget::errors::Error),
+    /// Errors of the sources that are not Phidgets.
+    Device(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Phidget(error) => write!(f, "{}", error),
+            Error::Device(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<phidget::errors::Error> for Error {
+    fn from(error: phidget::errors::Error) -> Self {
+        Error::Phidget(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Device(error.to_string())
+    }
+}
+
+/// Connects to the sensor wherever its readings come from.
+pub fn connect(config: &Config) -> impl Straw<(), Event, Error> {
+    let config = config.clone();
+
+    sipper(async move |events| match config.source {
+        Source::Phidget => {
+            connect_temperature(config.hub_port, config.serial_number, config.channel)
+                .run(events)
+                .await
+        }
+        Source::Modbus(modbus) => modbus::connect(modbus).run(events).await,
+        Source::Serial(serial) => serial::connect(serial).run(events).await,
+    })
+}
+
 pub fn connect_temperature(
     hub_port: i32,
     serial_number: i32,
     channel: i32,
 ) -> impl Straw<(), Event, Error> {
     sipper(async move |mut event| {
-        let mut sensor = TemperatureSensor::new();
+        let mut sensor = Channel(TemperatureSensor::new());
 
         sensor.set_hub_port(hub_port)?;
         sensor.set_serial_number(serial_number)?;
         sensor.set_channel(channel)?;
 
-        let (tx, rx) = mpsc::channel();
+        let (tx, mut rx) = mpsc::unbounded();
 
         let tx1 = tx.clone();
         let tx2 = tx.clone();
 
+        // The handlers run on Phidget threads and may outlive the receiver,
+        // so a closed channel is ignored instead of unwrapped.
         sensor.set_on_temperature_change_handler(move |_, t: f64| {
-            tx.send(Event::Change(TempData::new(t))).unwrap();
+            let _ = tx.unbounded_send(Ok(Event::Change(TempData::new(t))));
         })?;
 
         sensor.set_on_attach_handler(move |s| {
-            s.set_thermocouple_type(ThermocoupleType::TypeJ).unwrap();
-            tx1.send(Event::Attach).unwrap();
+            let result = s
+                .set_thermocouple_type(ThermocoupleType::TypeJ)
+                .map(|_| Event::Attach);
+            let _ = tx1.unbounded_send(result);
         })?;
 
         sensor.set_on_detach_handler(move |_| {
-            tx2.send(Event::Detach).unwrap();
+            let _ = tx2.unbounded_send(Ok(Event::Detach));
         })?;
 
-        sensor.open_wait(TIMEOUT_DEFAULT)?;
+        sensor.open()?;
 
-        while let Ok(ev) = rx.recv() {
-            match ev {
+        match tokio::time::timeout(TIMEOUT_DEFAULT, rx.next()).await {
+            Ok(Some(ev)) => event.send(ev?).await,
+            Ok(None) => return Ok(()),
+            Err(_) => {
+                return Err(Error::Device(
+                    "The Phidget channel did not attach in time".to_string(),
+                ));
+            }
+        }
+
+        while let Some(ev) = rx.next().await {
+            match ev? {
                 Event::Detach => {
-                    event.send(ev).await;
+                    event.send(Event::Detach).await;
                     break;
                 }
-                _ => {
+                ev => {
                     event.send(ev).await;
                 }
             }
@@ -58,6 +122,30 @@ pub fn connect_temperature(
     })
 }
 
+/// Closes the Phidget handle when the connection is dropped, which happens
+/// when its task is aborted or the application exits.
+struct Channel(TemperatureSensor);
+
+impl Deref for Channel {
+    type Target = TemperatureSensor;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Channel {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        let _ = self.0.close();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Event {
     Change(TempData),
@@ -72,7 +160,7 @@ pub struct TempData {
 }
 
 impl TempData {
-    fn new(temp: f64) -> Self {
+    pub fn new(temp: f64) -> Self {
         Self {
             temp,
             time: Instant::now(),
@@ -85,3 +173,173 @@ impl Default for TempData {
         Self::new(0.0)
     }
 }
+
+/// Where the readings of a sensor come from. The Phidget channel is given by
+/// the `hub_port`, `serial_number` and `channel` of the configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Source {
+    #[default]
+    Phidget,
+    Modbus(modbus::Config),
+    Serial(serial::Config),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub name: String,
+    pub color: [f32; 3],
+    pub hub_port: i32,
+    pub serial_number: i32,
+    pub channel: i32,
+    #[serde(default)]
+    pub filter: filter::Settings,
+    #[serde(default)]
+    pub source: Source,
+    /// Name of the roaster the sensor is on, the first one when empty.
+    #[serde(default)]
+    pub machine: String,
+}
+
+impl Config {
+    /// Where the sensor is connected, for the settings.
+    pub fn location(&self) -> String {
+        match &self.source {
+            Source::Phidget => format!(
+                "serial {}, port {}, channel {}",
+                self.serial_number, self.hub_port, self.channel
+            ),
+            Source::Modbus(modbus) => modbus.to_string(),
+            Source::Serial(serial) => serial.to_string(),
+        }
+    }
+
+    pub fn defaults() -> Vec<Config> {
+        vec![
+            Config {
+                name: "Bean".to_string(),
+                color: [0., 0.5, 1.],
+                hub_port: 0,
+                serial_number: 572104,
+                channel: 0,
+                filter: filter::Settings::default(),
+                source: Source::Phidget,
+                machine: String::new(),
+            },
+            Config {
+                name: "Exhaust".to_string(),
+                color: [1., 0., 0.],
+                hub_port: 0,
+                serial_number: 572104,
+                channel: 1,
+                filter: filter::Settings::default(),
+                source: Source::Phidget,
+                machine: String::new(),
+            },
+        ]
+    }
+}
+
+/// Closes the manager when the scan ends or is aborted.
+struct Scan(Manager);
+
+impl Deref for Scan {
+    type Target = Manager;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Scan {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        let _ = self.0.close();
+    }
+}
+
+pub fn discover() -> impl Straw<(), Discovery, Error> {
+    sipper(async move |mut event| {
+        let mut manager = Scan(Manager::new());
+
+        let (tx, mut rx) = mpsc::unbounded();
+        let tx1 = tx.clone();
+
+        manager.set_on_attach_handler(move |phidget: &GenericPhidget| {
+            if let Ok(device) = Device::read(phidget) {
+                let _ = tx.unbounded_send(Discovery::Attached(device));
+            }
+        })?;
+
+        manager.set_on_detach_handler(move |phidget: &GenericPhidget| {
+            if let Ok(device) = Device::read(phidget) {
+                let _ = tx1.unbounded_send(Discovery::Detached(device));
+            }
+        })?;
+
+        manager.open()?;
+
+        while let Some(discovery) = rx.next().await {
+            event.send(discovery).await;
+        }
+
+        Ok(())
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Discovery {
+    Attached(Device),
+    Detached(Device),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Hub,
+    VintPort,
+    Temperature,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub kind: DeviceKind,
+    pub name: String,
+    pub serial_number: i32,
+    pub hub_port: i32,
+    pub channel: i32,
+}
+
+impl Device {
+    fn read(phidget: &GenericPhidget) -> Result<Self, Error> {
+        let kind = if phidget.device_class()? == DeviceClass::Hub {
+            DeviceKind::Hub
+        } else if phidget.channel_class()? == ChannelClass::TemperatureSensor {
+            DeviceKind::Temperature
+        } else if phidget.is_hub_port_device()? {
+            DeviceKind::VintPort
+        } else {
+            DeviceKind::Other
+        };
+
+        Ok(Device {
+            kind,
+            name: phidget.device_name()?,
+            serial_number: phidget.serial_number()?,
+            hub_port: phidget.hub_port()?,
+            channel: phidget.channel()?,
+        })
+    }
+
+    pub fn matches(&self, config: &Config) -> bool {
+        matches!(config.source, Source::Phidget)
+            && self.serial_number == config.serial_number
+            && self.hub_port == config.hub_port
+            && self.channel == config.channel
+    }
+}
@@ -0,0 +1,247 @@
+//! Thermometers on a serial or USB port.
+//!
+//! - `Lines`: Arduino sketches printing one reading per line, such as the
+//!   MAX31855 examples (`C = 201.50`) or comma separated channels
+//!   (`201.5,180.2`). The channel picks the n-th number of the matching lines.
+//! - `Center309`: the meter is asked for its 45 bytes status, the channel
+//!   picks one of its T1 to T4 inputs.
+//!
+//! Try it with a pseudo-terminal: `socat -d -d pty,raw,echo=0 pty,raw,echo=0`,
+//! add a sensor on the first pty and `echo "C = 201.5" > /dev/pts/<second>`.
+
+use iced::task::{Straw, sipper};
+use serde::{Deserialize, Serialize};
+use std::{fmt, io, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    time::{self, MissedTickBehavior},
+};
+use tokio_serial::SerialStream;
+
+use crate::sensor::{Error, Event, TempData};
+
+/// How long to wait for a reading before giving up on the connection. Most
+/// Arduinos reset when the port is opened and take a few seconds to print.
+const TIMEOUT: Duration = Duration::from_secs(5);
+const CENTER_309_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Protocol {
+    Lines,
+    Center309,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 2] = [Protocol::Lines, Protocol::Center309];
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Lines => write!(f, "Text lines (Arduino)"),
+            Protocol::Center309 => write!(f, "Center 309"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub path: String,
+    pub baud_rate: u32,
+    pub protocol: Protocol,
+    /// Only lines starting with it are read, e.g. `C =`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Starts at 1.
+    pub channel: usize,
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on {} at {} baud, channel {}",
+            self.protocol, self.path, self.baud_rate, self.channel
+        )
+    }
+}
+
+fn no_reading() -> Error {
+    Error::Device("No reading from the serial port".to_string())
+}
+
+impl Config {
+    /// Reads the temperature of the channel from a line, if it has one.
+    fn parse(&self, line: &str) -> Option<f64> {
+        let line = line.trim().strip_prefix(self.prefix.trim())?;
+
+        line.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '=' | ':'))
+            .filter_map(|token| token.trim_end_matches(['C', 'c', '°']).parse::<f64>().ok())
+            .nth(self.channel.checked_sub(1)?)
+            .filter(|temp| temp.is_finite())
+    }
+
+    /// The 45 bytes response starts with STX, the inputs are signed 16 bits
+    /// big-endian tenths of a degree from byte 7.
+    fn center_309(&self, response: &[u8]) -> Result<f64, Error> {
+        if response.len() != 45 || response[0] != 0x02 {
+            return Err(Error::Device(
+                "Unexpected response from the Center 309".to_string(),
+            ));
+        }
+        let index = self
+            .channel
+            .checked_sub(1)
+            .filter(|channel| *channel < 4)
+            .map(|channel| 7 + 2 * channel)
+            .ok_or_else(|| {
+                Error::Device(format!("The Center 309 has no channel {}", self.channel))
+            })?;
+
+        let value = i16::from_be_bytes([response[index], response[index + 1]]);
+        Ok(value as f64 / 10.0)
+    }
+}
+
+pub fn connect(config: Config) -> impl Straw<(), Event, Error> {
+    sipper(async move |mut events| {
+        let mut port = SerialStream::open(&tokio_serial::new(&config.path, config.baud_rate))
+            .map_err(io::Error::from)?;
+        let mut attached = false;
+
+        match config.protocol {
+            Protocol::Lines => {
+                let mut reader = BufReader::new(port);
+                let mut line = Vec::new();
+
+                loop {
+                    line.clear();
+                    let read = time::timeout(TIMEOUT, reader.read_until(b'\n', &mut line))
+                        .await
+                        .map_err(|_| no_reading())??;
+                    if read == 0 {
+                        return Ok(());
+                    }
+
+                    if let Some(temp) = config.parse(&String::from_utf8_lossy(&line)) {
+                        if !attached {
+                            attached = true;
+                            events.send(Event::Attach).await;
+                        }
+                        events.send(Event::Change(TempData::new(temp))).await;
+                    }
+                }
+            }
+            Protocol::Center309 => {
+                let mut interval = time::interval(CENTER_309_INTERVAL);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                let mut response = [0; 45];
+
+                loop {
+                    interval.tick().await;
+                    port.write_all(b"A").await?;
+                    time::timeout(TIMEOUT, port.read_exact(&mut response))
+                        .await
+                        .map_err(|_| no_reading())??;
+
+                    let temp = config.center_309(&response)?;
+                    if !attached {
+                        attached = true;
+                        events.send(Event::Attach).await;
+                    }
+                    events.send(Event::Change(TempData::new(temp))).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(protocol: Protocol, prefix: &str, channel: usize) -> Config {
+        Config {
+            path: "/dev/null".to_string(),
+            baud_rate: 9600,
+            protocol,
+            prefix: prefix.to_string(),
+            channel,
+        }
+    }
+
+    #[test]
+    fn max31855_lines() {
+        let config = config(Protocol::Lines, "C =", 1);
+
+        assert_eq!(config.parse("C = 201.50\r\n"), Some(201.5));
+        assert_eq!(config.parse("F = 394.70"), None);
+        assert_eq!(config.parse("C = nan"), None);
+    }
+
+    #[test]
+    fn channels() {
+        let first = config(Protocol::Lines, "", 1);
+        let second = config(Protocol::Lines, "", 2);
+        let third = config(Protocol::Lines, "", 3);
+
+        assert_eq!(first.parse("201.5,180.2"), Some(201.5));
+        assert_eq!(second.parse("201.5,180.2"), Some(180.2));
+        assert_eq!(second.parse("BT: 201.5C; ET: -12.0°"), Some(-12.0));
+        assert_eq!(third.parse("201.5,180.2"), None);
+        assert_eq!(config(Protocol::Lines, "", 0).parse("201.5"), None);
+    }
+
+    fn response(channels: [i16; 4]) -> [u8; 45] {
+        let mut response = [0; 45];
+        response[0] = 0x02;
+        for (i, value) in channels.into_iter().enumerate() {
+            response[7 + 2 * i..9 + 2 * i].copy_from_slice(&value.to_be_bytes());
+        }
+        response
+    }
+
+    #[test]
+    fn center_309() {
+        let response = response([2015, -123, 0, 14000]);
+
+        assert_eq!(
+            config(Protocol::Center309, "", 1)
+                .center_309(&response)
+                .ok(),
+            Some(201.5)
+        );
+        assert_eq!(
+            config(Protocol::Center309, "", 2)
+                .center_309(&response)
+                .ok(),
+            Some(-12.3)
+        );
+        assert_eq!(
+            config(Protocol::Center309, "", 4)
+                .center_309(&response)
+                .ok(),
+            Some(1400.0)
+        );
+        assert!(
+            config(Protocol::Center309, "", 5)
+                .center_309(&response)
+                .is_err()
+        );
+        assert!(
+            config(Protocol::Center309, "", 0)
+                .center_309(&response)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn center_309_frame() {
+        let config = config(Protocol::Center309, "", 1);
+        let mut response = response([2015, 0, 0, 0]);
+
+        assert!(config.center_309(&response[..44]).is_err());
+        response[0] = 0x00;
+        assert!(config.center_309(&response).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Read-only API for displays on the local network.
+//!
+//! - `GET /api/live`: latest sensor readings and the state of the roast
+//! - `GET /api/roast`: all the curves of the roast in progress
+//! - `GET /api/roasts`: saved roasts
+//! - `GET /api/roasts/{name}`: a saved roast file
+//! - `GET /api/ws`: a WebSocket receiving `/api/live` on every reading
+//!
+//! Try it with `curl localhost:8421/api/live` or
+//! `websocat ws://localhost:8421/api/ws`.
+
+use axum::{
+    Json, Router,
+    extract::{self, State, WebSocketUpgrade, ws},
+    http::StatusCode,
+    response::Response,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    net::Ipv4Addr,
+    sync::{Arc, RwLock},
+};
+use tokio::{net::TcpListener, sync::broadcast, task};
+
+use crate::archive::{self, Event, RawRoastData};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub enabled: bool,
+    pub port: u16,
+    /// Listen on every interface instead of localhost only.
+    pub lan: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false,
+            port: 8421,
+            lan: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reading {
+    pub name: String,
+    pub temp: Option<f64>,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub recipe: Option<String>,
+    pub recording: bool,
+    /// Recording before charge, `elapsed` counts from the start of the
+    /// recording then.
+    pub preheating: bool,
+    /// Seconds since charge.
+    pub elapsed: f32,
+    /// Bean rate of rise in °C/min.
+    pub rate_of_rise: Option<f64>,
+    pub next_step: Option<String>,
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Live {
+    pub sensors: Vec<Reading>,
+    pub roast: Option<Status>,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    name: String,
+    started_at: String,
+    recipe: Option<String>,
+    total_time: f32,
+    weight_loss: Option<f32>,
+}
+
+/// State shared between the application and the server.
+#[derive(Debug, Clone)]
+pub struct Hub {
+    live: Arc<RwLock<Live>>,
+    roast: Arc<RwLock<Option<RawRoastData>>>,
+    updates: broadcast::Sender<Live>,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Hub {
+            live: Arc::default(),
+            roast: Arc::default(),
+            updates: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl Hub {
+    pub fn publish(&self, live: Live) {
+        if let Ok(mut current) = self.live.write() {
+            *current = live.clone();
+        }
+        let _ = self.updates.send(live);
+    }
+
+    pub fn publish_roast(&self, roast: Option<RawRoastData>) {
+        if let Ok(mut current) = self.roast.write() {
+            *current = roast;
+        }
+    }
+}
+
+async fn live(State(hub): State<Hub>) -> Json<Live> {
+    Json(hub.live.read().map(|live| live.clone()).unwrap_or_default())
+}
+
+async fn roast(State(hub): State<Hub>) -> Result<Json<RawRoastData>, StatusCode> {
+    hub.roast
+        .read()
+        .ok()
+        .and_then(|roast| roast.clone())
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Saved roasts are read from disk outside of the async runtime.
+async fn roasts() -> Result<Json<Vec<Summary>>, StatusCode> {
+    let entries = task::spawn_blocking(archive::list)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(|entry| Summary {
+                started_at: entry.roast.start.started_at.to_rfc3339(),
+                recipe: entry.roast.start.recipe.clone(),
+                total_time: entry.roast.metrics().total_time,
+                weight_loss: entry.roast.weight_loss,
+                name: entry.name,
+            })
+            .collect(),
+    ))
+}
+
+/// Whether `name` is the name of a saved roast, which keeps requests
+/// inside the data directory.
+fn is_roast_name(name: &str) -> bool {
+    name.starts_with("roast_")
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+async fn download(
+    extract::Path(name): extract::Path<String>,
+) -> Result<Json<RawRoastData>, StatusCode> {
+    if !is_roast_name(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let path = archive::dir().join(format!("{}.json", name));
+    task::spawn_blocking(move || archive::load(path).ok().map(|entry| entry.roast))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn websocket(upgrade: WebSocketUpgrade, State(hub): State<Hub>) -> Response {
+    upgrade.on_upgrade(move |socket| stream(socket, hub))
+}
+
+async fn stream(mut socket: ws::WebSocket, hub: Hub) {
+    let mut updates = hub.updates.subscribe();
+
+    loop {
+        match updates.recv().await {
+            Ok(live) => {
+                let Ok(json) = serde_json::to_string(&live) else {
+                    continue;
+                };
+                if socket.send(ws::Message::Text(json.into())).await.is_err() {
+                    break;
+                }
+            }
+            // A slow display just skips readings.
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Serves the API until the task is aborted or the listener fails.
+pub async fn serve(config: Config, hub: Hub) -> Result<(), String> {
+    let address = if config.lan {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let listener = TcpListener::bind((address, config.port))
+        .await
+        .map_err(|error| error.to_string())?;
+
+    axum::serve(listener, router(hub))
+        .await
+        .map_err(|error| error.to_string())
+}
+
+fn router(hub: Hub) -> Router {
+    Router::new()
+        .route("/api/live", get(live))
+        .route("/api/roast", get(roast))
+        .route("/api/roasts", get(roasts))
+        .route("/api/roasts/{name}", get(download))
+        .route("/api/ws", get(websocket))
+        .with_state(hub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Sends a GET request to a local server, returning the whole response.
+    async fn get(port: u16, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+            .await
+            .unwrap();
+        stream
+            .write_all(
+                format!(
+                    "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    path
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn roast_names() {
+        assert!(is_roast_name("roast_18-10-2026_09h30m12_Roaster"));
+        assert!(!is_roast_name("roast_../preferences"));
+        assert!(!is_roast_name("preferences"));
+        assert!(!is_roast_name("roast_a/b"));
+    }
+
+    #[tokio::test]
+    async fn live_readings() {
+        let hub = Hub::default();
+        hub.publish(Live {
+            sensors: vec![Reading {
+                name: "Bean".to_string(),
+                temp: Some(201.5),
+                connected: true,
+            }],
+            roast: None,
+        });
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, router(hub)).await });
+
+        let response = get(port, "/api/live").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(
+            response.ends_with(
+                r#"{"sensors":[{"name":"Bean","temp":201.5,"connected":true}],"roast":null}"#
+            ),
+            "{}",
+            response
+        );
+
+        assert!(get(port, "/api/roast").await.starts_with("HTTP/1.1 404"));
+        assert!(
+            get(port, "/api/roasts/roast_..%2Fpreferences")
+                .await
+                .starts_with("HTTP/1.1 404")
+        );
+    }
+}
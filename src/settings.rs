@@ -1,36 +1,293 @@
 use iced::{
-    Element,
+    Alignment, Element,
     Length::Fill,
-    Theme,
-    widget::{center, column, pick_list, text},
+    Task, Theme,
+    widget::{
+        button, center, checkbox, column, horizontal_space, pick_list, row, scrollable, slider,
+        text, text_input,
+    },
 };
 
-use crate::preferences::Preferences;
+use crate::{
+    discovery::{self, Discovery},
+    filter, manual, mqtt,
+    preferences::Preferences,
+    sensor, server,
+};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct Settings {
     preferences: Preferences,
+    discovery: Discovery,
+    manual: manual::Form,
+    machine: String,
+    machine_error: Option<String>,
+    port: String,
+    server_error: Option<String>,
+    mqtt_host: String,
+    mqtt_port: String,
+    mqtt_prefix: String,
+    mqtt_error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ThemeSelected(Theme),
+    FilterChanged(usize, filter::Settings),
+    /// The smoothing is only applied once its slider is released.
+    SmoothingChanged(usize, f64),
+    SmoothingReleased(usize),
+    RemoveSensor(usize),
+    SensorMachineSelected(usize, String),
+    MachineChanged(String),
+    AddMachine,
+    RemoveMachine(usize),
+    Discovery(discovery::Message),
+    Manual(manual::Message),
+    ServerToggled(bool),
+    ServerLanToggled(bool),
+    ServerPortChanged(String),
+    MqttToggled(bool),
+    MqttHostChanged(String),
+    MqttPortChanged(String),
+    MqttPrefixChanged(String),
+    MqttQosSelected(mqtt::Qos),
+    MqttSubmitted,
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    SensorAdded(sensor::Config),
+    SensorRemoved(usize),
+    SensorMoved(usize, sensor::Config),
+    MachineAdded(String),
+    /// Asks to remove a machine, see `remove_machine`.
+    RemoveMachine(String),
+    FilterChanged(usize, filter::Settings),
+    ServerChanged(server::Config),
+    MqttChanged(mqtt::Config),
 }
 
 impl Settings {
     pub fn new(preferences: Preferences) -> Self {
-        Settings { preferences }
+        Settings {
+            port: preferences.server.port.to_string(),
+            mqtt_host: preferences.mqtt.host.clone(),
+            mqtt_port: preferences.mqtt.port.to_string(),
+            mqtt_prefix: preferences.mqtt.prefix.clone(),
+            preferences,
+            discovery: Discovery::default(),
+            manual: manual::Form::default(),
+            machine: String::new(),
+            machine_error: None,
+            server_error: None,
+            mqtt_error: None,
+        }
+    }
+
+    pub fn server(&self) -> &server::Config {
+        &self.preferences.server
+    }
+
+    /// Forgets a machine once its roaster was removed, a roaster with a roast
+    /// in progress is kept.
+    pub fn remove_machine(&mut self, name: &str, removed: bool) {
+        if removed {
+            self.preferences.machines.retain(|machine| machine != name);
+            self.preferences.save().ok();
+            self.machine_error = None;
+        } else {
+            self.machine_error = Some(format!(
+                "{} has a roast in progress, save or discard it first",
+                name
+            ));
+        }
+    }
+
+    pub fn set_server_error(&mut self, error: Option<String>) {
+        self.server_error = error;
+    }
+
+    fn filter_changed(&mut self, id: usize) -> Action {
+        let Some(sensor) = self.preferences.sensors.get(id) else {
+            return Action::None;
+        };
+        let filter = sensor.filter.clone();
+        self.preferences.save().ok();
+        Action::FilterChanged(id, filter)
+    }
+
+    fn server_changed(&mut self) -> Action {
+        self.server_error = None;
+        self.preferences.save().ok();
+        Action::ServerChanged(self.preferences.server.clone())
+    }
+
+    /// The roaster of a sensor, the first one when its machine is unknown.
+    fn machine_of(&self, sensor: &sensor::Config) -> Option<&String> {
+        let machines = &self.preferences.machines;
+        machines
+            .iter()
+            .find(|machine| **machine == sensor.machine)
+            .or(machines.first())
+    }
+
+    pub fn mqtt(&self) -> &mqtt::Config {
+        &self.preferences.mqtt
+    }
+
+    pub fn set_mqtt_error(&mut self, error: Option<String>) {
+        self.mqtt_error = error;
+    }
+
+    fn mqtt_changed(&mut self) -> Action {
+        self.mqtt_error = None;
+        self.preferences.save().ok();
+        Action::MqttChanged(self.preferences.mqtt.clone())
     }
 
     pub fn theme(&self) -> Theme {
         self.preferences.theme.clone()
     }
 
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::ThemeSelected(theme) => {
                 self.preferences.theme = theme;
                 self.preferences.save().ok();
+                Action::None
+            }
+            Message::FilterChanged(id, filter) => {
+                if let Some(sensor) = self.preferences.sensors.get_mut(id) {
+                    sensor.filter = filter;
+                }
+                self.filter_changed(id)
+            }
+            Message::SmoothingChanged(id, smoothing) => {
+                if let Some(sensor) = self.preferences.sensors.get_mut(id) {
+                    sensor.filter.smoothing = smoothing;
+                }
+                Action::None
+            }
+            Message::SmoothingReleased(id) => self.filter_changed(id),
+            Message::RemoveSensor(id) => {
+                if id < self.preferences.sensors.len() {
+                    self.preferences.sensors.remove(id);
+                    self.preferences.save().ok();
+                    Action::SensorRemoved(id)
+                } else {
+                    Action::None
+                }
+            }
+            Message::SensorMachineSelected(id, machine) => {
+                match self.preferences.sensors.get_mut(id) {
+                    Some(sensor) if sensor.machine != machine => {
+                        sensor.machine = machine;
+                        let config = sensor.clone();
+                        self.preferences.save().ok();
+                        Action::SensorMoved(id, config)
+                    }
+                    _ => Action::None,
+                }
+            }
+            Message::MachineChanged(machine) => {
+                self.machine = machine;
+                Action::None
+            }
+            Message::AddMachine => {
+                let name = self.machine.trim().to_string();
+                if name.is_empty() || self.preferences.machines.contains(&name) {
+                    return Action::None;
+                }
+                self.machine.clear();
+                self.preferences.machines.push(name.clone());
+                self.preferences.save().ok();
+                Action::MachineAdded(name)
+            }
+            Message::RemoveMachine(id) => {
+                let Some(name) = self.preferences.machines.get(id).cloned() else {
+                    return Action::None;
+                };
+                let in_use = self
+                    .preferences
+                    .sensors
+                    .iter()
+                    .any(|sensor| self.machine_of(sensor) == Some(&name));
+                if in_use || self.preferences.machines.len() < 2 {
+                    return Action::None;
+                }
+                Action::RemoveMachine(name)
+            }
+            Message::ServerToggled(enabled) => {
+                self.preferences.server.enabled = enabled;
+                self.server_changed()
+            }
+            Message::ServerLanToggled(lan) => {
+                self.preferences.server.lan = lan;
+                self.server_changed()
+            }
+            Message::ServerPortChanged(port) => {
+                let parsed = port.trim().parse();
+                self.port = port;
+                match parsed {
+                    Ok(port) if port != self.preferences.server.port => {
+                        self.preferences.server.port = port;
+                        self.server_changed()
+                    }
+                    _ => Action::None,
+                }
+            }
+            Message::Manual(message) => {
+                match self.manual.update(message, &self.preferences.sensors) {
+                    Some(config) => {
+                        self.preferences.sensors.push(config.clone());
+                        self.preferences.save().ok();
+                        Action::SensorAdded(config)
+                    }
+                    None => Action::None,
+                }
+            }
+            Message::MqttToggled(enabled) => {
+                self.preferences.mqtt.enabled = enabled;
+                self.mqtt_changed()
+            }
+            Message::MqttHostChanged(host) => {
+                self.mqtt_host = host;
+                Action::None
+            }
+            Message::MqttPortChanged(port) => {
+                self.mqtt_port = port;
+                Action::None
+            }
+            Message::MqttPrefixChanged(prefix) => {
+                self.mqtt_prefix = prefix;
+                Action::None
+            }
+            Message::MqttQosSelected(qos) => {
+                self.preferences.mqtt.qos = qos;
+                self.mqtt_changed()
+            }
+            Message::MqttSubmitted => {
+                let Ok(port) = self.mqtt_port.trim().parse() else {
+                    self.mqtt_error = Some("The broker port must be a number".to_string());
+                    return Action::None;
+                };
+                self.preferences.mqtt.host = self.mqtt_host.trim().to_string();
+                self.preferences.mqtt.port = port;
+                self.preferences.mqtt.prefix = self.mqtt_prefix.trim().to_string();
+                self.mqtt_changed()
+            }
+            Message::Discovery(message) => {
+                let (task, added) = self.discovery.update(message, &self.preferences.sensors);
+
+                if let Some(config) = added {
+                    self.preferences.sensors.push(config.clone());
+                    self.preferences.save().ok();
+                    Action::SensorAdded(config)
+                } else {
+                    Action::Run(task.map(Message::Discovery))
+                }
             }
         }
     }
@@ -47,9 +304,230 @@ impl Settings {
         ]
         .spacing(10);
 
-        let content: Element<'_, Message> =
-            center(column![choose_theme].spacing(20).padding(20).max_width(600)).into();
+        let sensors = column![text("Sensors:")]
+            .extend(
+                self.preferences
+                    .sensors
+                    .iter()
+                    .enumerate()
+                    .map(|(id, sensor)| {
+                        row![
+                            text(format!("{} ({})", sensor.name, sensor.location())),
+                            horizontal_space(),
+                            pick_list(
+                                self.preferences.machines.as_slice(),
+                                self.machine_of(sensor),
+                                move |machine| Message::SensorMachineSelected(id, machine)
+                            )
+                            .text_size(14),
+                            button(text("Remove").size(14))
+                                .on_press(Message::RemoveSensor(id))
+                                .style(button::danger),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center)
+                        .into()
+                    }),
+            )
+            .spacing(10);
+
+        let machines = column![text("Roasters:")]
+            .extend(
+                self.preferences
+                    .machines
+                    .iter()
+                    .enumerate()
+                    .map(|(id, machine)| {
+                        let in_use = self
+                            .preferences
+                            .sensors
+                            .iter()
+                            .any(|sensor| self.machine_of(sensor) == Some(machine));
+
+                        row![
+                            text(machine),
+                            horizontal_space(),
+                            button(text("Remove").size(14))
+                                .on_press_maybe(
+                                    (!in_use && self.preferences.machines.len() > 1)
+                                        .then_some(Message::RemoveMachine(id))
+                                )
+                                .style(button::danger),
+                        ]
+                        .align_y(Alignment::Center)
+                        .into()
+                    }),
+            )
+            .push(
+                row![
+                    text_input("Name", &self.machine)
+                        .on_input(Message::MachineChanged)
+                        .on_submit(Message::AddMachine)
+                        .width(Fill),
+                    button(text("Add roaster").size(14)).on_press(Message::AddMachine),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center),
+            )
+            .push_maybe(
+                self.machine_error
+                    .as_ref()
+                    .map(|error| text(error).style(text::danger)),
+            )
+            .spacing(10);
+
+        let filters = column(
+            self.preferences
+                .sensors
+                .iter()
+                .enumerate()
+                .map(|(id, sensor)| {
+                    let filter = &sensor.filter;
+
+                    column![
+                        text(format!("{} filter:", sensor.name)),
+                        row![
+                            text("Median window").width(Fill),
+                            pick_list(filter::MEDIAN_WINDOWS, Some(filter.median), move |median| {
+                                Message::FilterChanged(
+                                    id,
+                                    filter::Settings {
+                                        median,
+                                        ..filter.clone()
+                                    },
+                                )
+                            }),
+                        ]
+                        .spacing(10),
+                        row![
+                            text(format!("Smoothing {:.2}", filter.smoothing)).width(Fill),
+                            slider(0.05..=1.0, filter.smoothing, move |smoothing| {
+                                Message::SmoothingChanged(id, smoothing)
+                            })
+                            .on_release(Message::SmoothingReleased(id))
+                            .step(0.05)
+                            .width(Fill),
+                        ]
+                        .spacing(10),
+                        row![
+                            text("Decimation").width(Fill),
+                            pick_list(
+                                filter::DECIMATIONS,
+                                Some(filter.decimation),
+                                move |decimation| {
+                                    Message::FilterChanged(
+                                        id,
+                                        filter::Settings {
+                                            decimation,
+                                            ..filter.clone()
+                                        },
+                                    )
+                                }
+                            ),
+                        ]
+                        .spacing(10),
+                    ]
+                    .spacing(10)
+                    .into()
+                }),
+        )
+        .spacing(20);
+
+        let discovery = self.discovery.view().map(Message::Discovery);
+        let manual = self.manual.view().map(Message::Manual);
+
+        let server = &self.preferences.server;
+        let api = column![
+            text("Live API:"),
+            checkbox("Serve live data over HTTP and WebSocket", server.enabled)
+                .on_toggle(Message::ServerToggled),
+            checkbox("Allow devices on the local network", server.lan)
+                .on_toggle(Message::ServerLanToggled),
+            row![
+                text("Port").width(Fill),
+                text_input("8421", &self.port)
+                    .on_input(Message::ServerPortChanged)
+                    .width(Fill),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        ]
+        .push_maybe(
+            self.server_error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10);
+
+        let broker = &self.preferences.mqtt;
+        let publisher = column![
+            text("MQTT:"),
+            checkbox("Publish readings and roast events", broker.enabled)
+                .on_toggle(Message::MqttToggled),
+            field(
+                "Broker",
+                "localhost",
+                &self.mqtt_host,
+                Message::MqttHostChanged
+            ),
+            field("Port", "1883", &self.mqtt_port, Message::MqttPortChanged),
+            field(
+                "Topic prefix",
+                "roaster",
+                &self.mqtt_prefix,
+                Message::MqttPrefixChanged
+            ),
+            row![
+                text("QoS").width(Fill),
+                pick_list(mqtt::Qos::ALL, Some(broker.qos), Message::MqttQosSelected).width(Fill),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            button(text("Apply").size(14)).on_press(Message::MqttSubmitted),
+        ]
+        .push_maybe(
+            self.mqtt_error
+                .as_ref()
+                .map(|error| text(error).style(text::danger)),
+        )
+        .spacing(10);
+
+        let content: Element<'_, Message> = center(scrollable(
+            column![
+                choose_theme,
+                machines,
+                sensors,
+                discovery,
+                manual,
+                filters,
+                api,
+                publisher
+            ]
+            .spacing(20)
+            .padding(20)
+            .max_width(600),
+        ))
+        .into();
 
         content.into()
     }
 }
+
+/// A labelled broker setting, applied on Enter.
+fn field<'a>(
+    label: &'a str,
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> Message,
+) -> Element<'a, Message> {
+    row![
+        text(label).width(Fill),
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .on_submit(Message::MqttSubmitted)
+            .width(Fill),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
@@ -0,0 +1,422 @@
+use iced::{
+    Alignment, Color, Element,
+    Length::Fill,
+    Task,
+    widget::{button, canvas, column, container, horizontal_space, pick_list, row, slider, text},
+};
+use std::collections::BTreeMap;
+use tokio::task;
+
+use crate::{
+    archive::{self, Entry, RawRoastData},
+    data,
+    roast::{CurveFit, CurveSettings, Overlay, Roast},
+};
+
+/// Seconds between two samples of the envelope.
+const STEP: f32 = 5.0;
+/// Fewest roasts needed at a given time to compute a band.
+const MIN_ROASTS: usize = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Band {
+    pub time: f32,
+    pub mean: f32,
+    pub stddev: f32,
+}
+
+/// Mean and standard deviation of the bean curve over time.
+#[derive(Debug, Clone, Default)]
+pub struct Envelope {
+    pub bands: Vec<Band>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Distribution {
+    pub mean: f32,
+    pub stddev: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Distribution {
+    pub fn new(values: impl Iterator<Item = f32>) -> Option<Self> {
+        let values: Vec<f32> = values.collect();
+        if values.is_empty() {
+            return None;
+        }
+
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+
+        Some(Distribution {
+            mean,
+            stddev: variance.sqrt(),
+            min: values.iter().copied().fold(f32::INFINITY, f32::min),
+            max: values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        })
+    }
+}
+
+impl Envelope {
+    pub fn new<'a>(roasts: impl Iterator<Item = &'a RawRoastData> + Clone) -> Self {
+        let end = roasts
+            .clone()
+            .map(|roast| roast.metrics().total_time)
+            .fold(0.0, f32::max);
+
+        let bands = (0..=(end / STEP) as usize)
+            .map(|step| step as f32 * STEP)
+            .filter_map(|time| {
+                let temps = roasts
+                    .clone()
+                    .filter_map(|roast| roast.bean()?.temp_at(time));
+                let distribution = Distribution::new(temps.clone())?;
+
+                (temps.count() >= MIN_ROASTS).then_some(Band {
+                    time,
+                    mean: distribution.mean,
+                    stddev: distribution.stddev,
+                })
+            })
+            .collect();
+
+        Envelope { bands }
+    }
+
+    /// The envelope of all saved roasts of `recipe`, read from disk outside
+    /// of the async runtime.
+    pub async fn for_recipe(recipe: String) -> Option<Self> {
+        task::spawn_blocking(move || {
+            let roasts = archive::list();
+            let envelope = Envelope::new(
+                roasts
+                    .iter()
+                    .map(|entry| &entry.roast)
+                    .filter(|roast| roast.start.recipe.as_ref() == Some(&recipe)),
+            );
+
+            (!envelope.bands.is_empty()).then_some(envelope)
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+}
+
+/// Largest distance in °C between the bean curve of each roast and the mean
+/// of the other roasts, by name. An outlier would otherwise pull the mean it
+/// is measured against towards itself.
+fn deviations<'a>(entries: impl Iterator<Item = &'a Entry> + Clone) -> BTreeMap<String, f32> {
+    let end = entries
+        .clone()
+        .map(|entry| entry.roast.metrics().total_time)
+        .fold(0.0, f32::max);
+    let times: Vec<f32> = (0..=(end / STEP) as usize)
+        .map(|step| step as f32 * STEP)
+        .collect();
+
+    let temps: Vec<(&Entry, Vec<Option<f32>>)> = entries
+        .map(|entry| {
+            let bean = entry.roast.bean();
+            (
+                entry,
+                times.iter().map(|time| bean?.temp_at(*time)).collect(),
+            )
+        })
+        .collect();
+    let (sums, counts): (Vec<f32>, Vec<usize>) = (0..times.len())
+        .map(|step| {
+            temps
+                .iter()
+                .filter_map(|(_, temps)| temps[step])
+                .fold((0.0, 0), |(sum, count), temp| (sum + temp, count + 1))
+        })
+        .unzip();
+
+    temps
+        .iter()
+        .filter_map(|(entry, temps)| {
+            let deviation = temps
+                .iter()
+                .enumerate()
+                .filter_map(|(step, temp)| {
+                    let temp = (*temp)?;
+                    let others = counts[step] - 1;
+                    (others >= MIN_ROASTS)
+                        .then(|| (temp - (sums[step] - temp) / others as f32).abs())
+                })
+                .reduce(f32::max)?;
+            Some((entry.name.clone(), deviation))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct Statistics {
+    roasts: Vec<Entry>,
+    recipes: Vec<String>,
+    recipe: Option<String>,
+    /// Allowed deviation from the mean curve, in °C.
+    tolerance: f32,
+    envelope: Envelope,
+    /// How far each roast is from the mean of the other ones, by name.
+    deviations: BTreeMap<String, f32>,
+    overlay: Overlay,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    RecipeSelected(String),
+    ToleranceChanged(f32),
+    Refresh,
+    Loaded(Vec<Entry>),
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics {
+            roasts: Vec::new(),
+            recipes: data::recipes()
+                .iter()
+                .map(|recipe| recipe.name().clone())
+                .collect(),
+            recipe: None,
+            tolerance: 5.0,
+            envelope: Envelope::default(),
+            deviations: BTreeMap::new(),
+            overlay: Overlay::default(),
+        }
+    }
+
+    /// Reads the saved roasts again, off the UI thread.
+    pub fn refresh(&self) -> Task<Message> {
+        Task::perform(archive::load_all(), Message::Loaded)
+    }
+
+    fn loaded(&mut self, roasts: Vec<Entry>) {
+        self.roasts = roasts;
+        self.rebuild();
+    }
+
+    fn selected(&self) -> impl Iterator<Item = &Entry> + Clone {
+        self.roasts.iter().filter(|entry| {
+            entry.roast.start.recipe.is_some() && entry.roast.start.recipe == self.recipe
+        })
+    }
+
+    fn deviation(&self, entry: &Entry) -> Option<f32> {
+        self.deviations.get(&entry.name).copied()
+    }
+
+    fn is_outlier(&self, entry: &Entry) -> bool {
+        self.deviation(entry)
+            .is_some_and(|deviation| deviation > self.tolerance)
+    }
+
+    fn rebuild(&mut self) {
+        self.envelope = Envelope::new(self.selected().map(|entry| &entry.roast));
+        self.deviations = deviations(self.selected());
+
+        let end = self
+            .selected()
+            .map(|entry| entry.roast.metrics().total_time)
+            .fold(17.0 * 60.0, f32::max);
+
+        let mut roasts: Vec<(Roast, Color)> = self
+            .selected()
+            .map(|entry| {
+                let mut roast = Roast::from_saved(&entry.roast, 0.0);
+                roast.curves.truncate(1);
+                roast.settings = CurveSettings {
+                    min: 0.0,
+                    max: end + 10.0,
+                    fit: CurveFit::Normal,
+                };
+                let color = if self.is_outlier(entry) {
+                    Color::from_rgb(1.0, 0.2, 0.2)
+                } else {
+                    Color::from_rgb(0.5, 0.5, 0.5)
+                };
+                (roast, color)
+            })
+            .collect();
+
+        if let Some((roast, _)) = roasts.first_mut() {
+            roast.envelope = Some(self.envelope.clone());
+        }
+
+        self.overlay.roasts = roasts;
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::RecipeSelected(recipe) => {
+                self.recipe = Some(recipe);
+                self.rebuild();
+            }
+            Message::ToleranceChanged(tolerance) => {
+                self.tolerance = tolerance;
+                self.rebuild();
+            }
+            Message::Refresh => return self.refresh(),
+            Message::Loaded(roasts) => self.loaded(roasts),
+        }
+
+        Task::none()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = text("Consistency").size(30);
+
+        let header = row![
+            pick_list(
+                self.recipes.as_slice(),
+                self.recipe.as_ref(),
+                Message::RecipeSelected
+            )
+            .placeholder("Select a recipe..."),
+            text(format!("Tolerance ±{:.1} °C", self.tolerance)),
+            slider(1.0..=20.0, self.tolerance, Message::ToleranceChanged)
+                .step(0.5)
+                .width(200),
+            horizontal_space(),
+            button("Refresh")
+                .on_press(Message::Refresh)
+                .style(button::secondary),
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center);
+
+        let distribution = |name: &str, distribution: Option<Distribution>, unit: &str| {
+            text(match distribution {
+                Some(d) => format!(
+                    "{}: {:.1} ± {:.1} {} (min {:.1}, max {:.1})",
+                    name, d.mean, d.stddev, unit, d.min, d.max
+                ),
+                None => format!("{}: –", name),
+            })
+        };
+
+        let summary = column![
+            text(format!("{} roasts", self.selected().count())),
+            distribution(
+                "Drop temperature",
+                Distribution::new(
+                    self.selected()
+                        .filter_map(|entry| Some(entry.roast.metrics().drop?.1))
+                ),
+                "°C"
+            ),
+            distribution(
+                "Total time",
+                Distribution::new(
+                    self.selected()
+                        .map(|entry| entry.roast.metrics().total_time / 60.0)
+                ),
+                "min"
+            ),
+        ]
+        .spacing(5);
+
+        let outliers = column![text("Outliers:").size(20)]
+            .extend(
+                self.selected()
+                    .filter(|entry| self.is_outlier(entry))
+                    .map(|entry| {
+                        text(format!(
+                            "{}: {:.1} °C off the mean curve",
+                            entry,
+                            self.deviation(entry).unwrap_or_default()
+                        ))
+                        .style(text::danger)
+                        .into()
+                    }),
+            )
+            .spacing(5);
+
+        container(
+            column![
+                title,
+                header,
+                canvas(&self.overlay).width(Fill).height(Fill),
+                row![summary, outliers].spacing(40),
+            ]
+            .spacing(20),
+        )
+        .padding(20)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{archive::RawCurveData, batch, filter, health};
+    use chrono::Local;
+    use std::path::PathBuf;
+
+    fn entry(name: &str, offset: f32) -> Entry {
+        let bean: Vec<(f32, f32)> = (0..=120)
+            .map(|step| {
+                let time = step as f32 * STEP;
+                (100.0 + time * 0.2 + offset, time)
+            })
+            .collect();
+
+        Entry {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            roast: RawRoastData {
+                start: batch::Start {
+                    lot: None,
+                    recipe: Some("House".to_string()),
+                    green_weight: 1.0,
+                    ambient_temp: None,
+                    humidity: None,
+                    operator: String::new(),
+                    machine: String::new(),
+                    order: None,
+                    planned: None,
+                    started_at: Local::now(),
+                },
+                end: None,
+                weight_loss: None,
+                events: Vec::new(),
+                data: vec![RawCurveData {
+                    id: 0,
+                    name: "Bean".to_string(),
+                    color: [0.0, 0.5, 1.0],
+                    raw: bean.clone(),
+                    points: bean,
+                    filter: filter::Settings::default(),
+                    health: health::Health::default().report(),
+                }],
+                preheat: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn outlier_is_measured_against_the_others() {
+        let entries = [
+            entry("a", 0.0),
+            entry("b", 0.0),
+            entry("c", 0.0),
+            entry("outlier", 20.0),
+        ];
+        let deviations = deviations(entries.iter());
+
+        assert!((deviations["outlier"] - 20.0).abs() < 0.01);
+        for name in ["a", "b", "c"] {
+            assert!((deviations[name] - 20.0 / 3.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn too_few_roasts() {
+        let entries = [entry("a", 0.0), entry("b", 10.0)];
+
+        assert!(deviations(entries.iter()).is_empty());
+    }
+}